@@ -15,21 +15,81 @@ pub use types::{Message, SystemPrompt};
 use uuid::Uuid;
 
 use crate::combobox::{Combobox, ComboboxItem};
+use crate::cost_tracker::{self, CostTracker};
+use crate::embeddings;
 use crate::llm::{self, DisplayModelInfo};
+use crate::persistence;
+use crate::tokenizer;
 use crate::GlobalState;
+use leptos_use::use_debounce_fn;
+use web_sys::js_sys::Date;
+
+/// Every `@name` mention in `input`, in the order they appear, paired with
+/// the `{{variable}}` values that prompt should expand with: the prompt's
+/// own `variables` defaults, overridden by any `key=value` words that
+/// follow the mention before the next one (e.g. `@review lang=rust` sets
+/// `lang` for `review`). A name mentioned more than once keeps only its
+/// first occurrence, so repeating `@review` doesn't duplicate it in the
+/// combined system message.
+fn extract_mentions(
+    input: &str,
+    system_prompts: &[SystemPrompt],
+) -> Vec<(SystemPrompt, std::collections::HashMap<String, String>)> {
+    let mut mentions: Vec<(SystemPrompt, std::collections::HashMap<String, String>)> = Vec::new();
+    for word in input.split_whitespace() {
+        if let Some(name) = word.strip_prefix('@') {
+            let name = name.trim_matches(|c: char| !c.is_alphanumeric());
+            if mentions.iter().any(|(sp, _)| sp.name == name) {
+                continue;
+            }
+            if let Some(sp) = system_prompts.iter().find(|sp| sp.name == name) {
+                let defaults = sp.variables.iter().cloned().collect();
+                mentions.push((sp.clone(), defaults));
+            }
+        } else if let Some((key, value)) = word.split_once('=') {
+            if let Some((_, vars)) = mentions.last_mut() {
+                vars.insert(key.to_string(), value.to_string());
+            }
+        }
+    }
+    mentions
+}
 
+/// The first `@`-mentioned prompt in `input`, ignoring any variable
+/// overrides. Used only to drive the single-selection auto-highlight
+/// effect below; `submit`/`regenerate` use [`extract_mentions`] directly
+/// to compose *all* mentioned prompts.
 fn extract_mentioned_prompt(input: &str, system_prompts: &[SystemPrompt]) -> Option<SystemPrompt> {
-    input
-        .split_whitespace()
-        .filter_map(|word| {
-            if let Some(name) = word.strip_prefix('@') {
-                let name = name.trim_matches(|c: char| !c.is_alphanumeric());
-                system_prompts.iter().find(|sp| sp.name == name).cloned()
-            } else {
-                None
-            }
-        })
+    extract_mentions(input, system_prompts)
+        .into_iter()
         .next()
+        .map(|(sp, _)| sp)
+}
+
+/// Names of every prompt currently `@`-mentioned in `input`, in mention
+/// order. Exposed so `SystemPromptBar` can highlight all of them, not just
+/// whichever one is the single `selected_prompt_name`.
+pub(crate) fn mentioned_prompt_names(input: &str, system_prompts: &[SystemPrompt]) -> Vec<String> {
+    extract_mentions(input, system_prompts)
+        .into_iter()
+        .map(|(sp, _)| sp.name)
+        .collect()
+}
+
+/// Expands each mentioned prompt's body independently against its own
+/// resolved variables, then joins the results in mention order. Expanding
+/// separately (rather than concatenating bodies first) keeps `/command`
+/// lines and `{{variable}}` placeholders scoped to the prompt that
+/// declared them.
+async fn render_mentions(
+    mentions: &[(SystemPrompt, std::collections::HashMap<String, String>)],
+) -> anyhow::Result<String> {
+    let mut parts = Vec::with_capacity(mentions.len());
+    for (sp, vars) in mentions {
+        let ctx = crate::prompt_expansion::ExpansionContext { vars: vars.clone() };
+        parts.push(crate::prompt_expansion::expand_prompt(&sp.prompt, &ctx).await?);
+    }
+    Ok(parts.join("\n\n"))
 }
 
 #[component]
@@ -90,7 +150,12 @@ pub fn ChatInterface(
         set_models_loading(true);
         set_models_error(None);
         spawn_local(async move {
-            match crate::llm::list_available_models(current_api_key).await {
+            match crate::llm::list_available_models(
+                state.provider_kind.get_untracked(),
+                current_api_key,
+            )
+            .await
+            {
                 Ok(models) => {
                     set_cached_models.set(models);
                 }
@@ -171,6 +236,121 @@ pub fn ChatInterface(
         }
     });
 
+    // Live token count for the composer: system prompt + history + whatever
+    // is currently typed. Recomputed on every change but debounced, since
+    // `tokenizer::estimate_tokens` re-walks the whole session each time.
+    let (token_count, set_token_count) = signal(0usize);
+    let recount_tokens = use_debounce_fn(
+        move || {
+            let mentioned = extract_mentions(&input.get_untracked(), &system_prompts.get_untracked());
+            let mut total = if mentioned.is_empty() {
+                selected_prompt
+                    .get_untracked()
+                    .map(|sp| tokenizer::estimate_tokens(&sp.prompt))
+                    .unwrap_or(0)
+            } else {
+                mentioned.iter().map(|(sp, _)| tokenizer::estimate_tokens(&sp.prompt)).sum()
+            };
+            for message in messages.get_untracked() {
+                total += tokenizer::estimate_tokens(&message.content);
+            }
+            total += tokenizer::estimate_tokens(&input.get_untracked());
+            set_token_count.set(total);
+        },
+        300.0,
+    );
+    Effect::new({
+        let recount_tokens = recount_tokens.clone();
+        move |_| {
+            let _ = input.get();
+            let _ = messages.get();
+            let _ = selected_prompt.get();
+            recount_tokens();
+        }
+    });
+
+    let context_limit = Memo::new(move |_| {
+        cached_models
+            .get()
+            .into_iter()
+            .find(|m| m.id == current_model_name.get())
+            .and_then(|m| m.context_length)
+    });
+
+    // --- Semantic retrieval across past sessions (see `crate::embeddings`) ---
+    let (retrieval_enabled, set_retrieval_enabled) = signal(false);
+    let (retrieval_k, set_retrieval_k) = signal(3usize);
+    // The snippets `submit` injected into the most recent request, so the
+    // collapsible block below the model bar can show the user what was
+    // used instead of doing retrieval invisibly.
+    let (last_retrieved, set_last_retrieved) = signal(Vec::<embeddings::RetrievedContext>::new());
+
+    // Lazily embeds each assistant reply (and the user turn that prompted
+    // it) once it's done streaming, so there's a growing corpus for
+    // `submit` to search. Fire-and-forget: a failed embedding just means
+    // that turn isn't retrievable later, not a chat-breaking error.
+    let (embedded_message_ids, set_embedded_message_ids) =
+        signal(std::collections::HashSet::<String>::new());
+    Effect::new(move |_| {
+        let current = messages.get();
+        // Still streaming; the effect re-runs once `input_disabled` clears.
+        if input_disabled.get() {
+            return;
+        }
+        let Some(last) = current.last() else {
+            return;
+        };
+        if last.role != "assistant" || last.interrupted || last.content.is_empty() {
+            return;
+        }
+        if embedded_message_ids.get_untracked().contains(&last.id) {
+            return;
+        }
+        if !retrieval_enabled.get_untracked() {
+            return;
+        }
+        let key = api_key.get_untracked();
+        if key.is_empty() {
+            return;
+        }
+        let Some(session_id) = state.current_session_id.get_untracked() else {
+            return;
+        };
+        set_embedded_message_ids.update(|seen| {
+            seen.insert(last.id.clone());
+        });
+
+        // The fresh reply plus the user turn that prompted it.
+        let to_embed: Vec<(String, String)> = current
+            .iter()
+            .rev()
+            .take(2)
+            .map(|m| (m.id.clone(), m.content.clone()))
+            .collect();
+
+        spawn_local(async move {
+            let texts: Vec<String> = to_embed.iter().map(|(_, content)| content.clone()).collect();
+            match embeddings::embed_texts(state.provider_kind.get_untracked(), texts, key).await {
+                Ok(vectors) => {
+                    for ((message_id, content), vector) in to_embed.into_iter().zip(vectors) {
+                        let record = persistence::EmbeddingRecord {
+                            message_id,
+                            session_id: session_id.clone(),
+                            content,
+                            vector,
+                            model: embeddings::EMBEDDING_MODEL.to_string(),
+                            created_at_ms: Date::now(),
+                        };
+                        if let Err(e) = persistence::save_embedding(&record).await {
+                            log!("[WARN] [Embeddings] Failed to save embedding: {e}");
+                        }
+                    }
+                }
+                Err(e) => log!("[WARN] [Embeddings] Failed to embed message: {e}"),
+            }
+        });
+    });
+
     let submit = Callback::new(move |prompt_override: Option<String>| {
         let content = prompt_override.unwrap_or_else(|| input.get());
         if content.is_empty() {
@@ -181,6 +361,10 @@ pub fn ChatInterface(
             model: current_model_name.get(),
             seed: None,
             temperature: Some(1.0),
+            provider: state.provider_kind.get_untracked(),
+            tools: Vec::new(),
+            max_completion_tokens: None,
+            timeout_secs: None,
         };
         let state = state.clone();
         spawn_local(async move {
@@ -197,18 +381,37 @@ pub fn ChatInterface(
             set_cancel_sender.set(Some(tx));
             set_input_disabled.set(true);
             set_error.set(None);
-            let system_prompt_content = selected_prompt
-                .get()
-                .map(|sp| sp.prompt)
-                .unwrap_or("".to_string());
+
+            // `@`-mentions typed into this message take priority; falling
+            // back to the single `selected_prompt` covers manual selection
+            // via `SystemPromptBar`/`PromptPicker` with no mention typed.
+            let mentions = {
+                let from_input = extract_mentions(&content, &system_prompts.get_untracked());
+                if from_input.is_empty() {
+                    selected_prompt
+                        .get_untracked()
+                        .map(|sp| {
+                            let vars = sp.variables.iter().cloned().collect();
+                            vec![(sp, vars)]
+                        })
+                        .unwrap_or_default()
+                } else {
+                    from_input
+                }
+            };
 
             let user_message = Message {
+                id: Uuid::new_v4().to_string(),
+                parent_id: messages.get_untracked().last().map(|m| m.id.clone()),
                 role: "user".to_string(),
                 content,
                 prompt_name: None,
                 system_prompt_content: None,
                 model_name: None,
                 cost: None,
+                interrupted: false,
+                diff_hunks: None,
+                retry_status: None,
             };
 
             set_input.set("".to_string());
@@ -224,31 +427,141 @@ pub fn ChatInterface(
                     "No API key provided. Please add one in Settings.".to_string(),
                 ));
             } else {
-                let mut messages_to_submit = Vec::new();
-                if !system_prompt_content.is_empty() {
-                    messages_to_submit.push(Message {
-                        role: "system".to_string(),
-                        content: system_prompt_content,
-                        prompt_name: selected_prompt.get().map(|sp| sp.name.clone()),
-                        system_prompt_content: selected_prompt.get().map(|sp| sp.prompt.clone()),
-                        model_name: Some(current_model_name.get()),
-                        cost: None,
-                    });
+                let expanded_system_prompt_content = if mentions.is_empty() {
+                    Ok(String::new())
+                } else {
+                    render_mentions(&mentions).await
+                };
+
+                let mentioned_name: Option<String> = (!mentions.is_empty()).then(|| {
+                    mentions.iter().map(|(sp, _)| sp.name.clone()).collect::<Vec<_>>().join(", ")
+                });
+                let mentioned_body: Option<String> = (!mentions.is_empty()).then(|| {
+                    mentions.iter().map(|(sp, _)| sp.prompt.clone()).collect::<Vec<_>>().join("\n\n")
+                });
+
+                match expanded_system_prompt_content {
+                    Ok(expanded_system_prompt_content) => {
+                        let mut messages_to_submit = Vec::new();
+                        if !expanded_system_prompt_content.is_empty() {
+                            messages_to_submit.push(Message {
+                                id: Uuid::new_v4().to_string(),
+                                parent_id: None,
+                                role: "system".to_string(),
+                                content: expanded_system_prompt_content,
+                                prompt_name: mentioned_name.clone(),
+                                system_prompt_content: mentioned_body.clone(),
+                                model_name: Some(current_model_name.get()),
+                                cost: None,
+                                interrupted: false,
+                                diff_hunks: None,
+                                retry_status: None,
+                            });
+                        }
+
+                        if retrieval_enabled.get_untracked() && !api_key.get_untracked().is_empty() {
+                            match embeddings::embed_texts(
+                                state.provider_kind.get_untracked(),
+                                vec![content.clone()],
+                                api_key.get_untracked(),
+                            )
+                            .await
+                            {
+                                Ok(vectors) => match vectors.into_iter().next() {
+                                    Some(query_vector) => match persistence::load_all_embeddings().await {
+                                        Ok(corpus) => {
+                                            let retrieved = embeddings::top_k_similar(
+                                                &query_vector,
+                                                &corpus,
+                                                retrieval_k.get_untracked(),
+                                            );
+                                            if !retrieved.is_empty() {
+                                                let joined = retrieved
+                                                    .iter()
+                                                    .map(|r| format!("--- retrieved (score {:.2}) ---\n{}", r.score, r.content))
+                                                    .collect::<Vec<_>>()
+                                                    .join("\n\n");
+                                                messages_to_submit.push(Message {
+                                                    id: Uuid::new_v4().to_string(),
+                                                    parent_id: None,
+                                                    role: "system".to_string(),
+                                                    content: format!(
+                                                        "Context retrieved from earlier conversations because it seemed relevant to the user's new message. Not part of the current conversation history.\n\n{joined}"
+                                                    ),
+                                                    prompt_name: Some("retrieved-context".to_string()),
+                                                    system_prompt_content: None,
+                                                    model_name: None,
+                                                    cost: None,
+                                                    interrupted: false,
+                                                    diff_hunks: None,
+                                                    retry_status: None,
+                                                });
+                                            }
+                                            set_last_retrieved.set(retrieved);
+                                        }
+                                        Err(e) => log!("[WARN] [Embeddings] Failed to load embeddings for retrieval: {e}"),
+                                    },
+                                    None => {}
+                                },
+                                Err(e) => log!("[WARN] [Embeddings] Failed to embed query for retrieval: {e}"),
+                            }
+                        } else {
+                            set_last_retrieved.set(Vec::new());
+                        }
+
+                        messages_to_submit.extend(messages.get());
+
+                        let over_budget = state.budget_ceiling_usd.get_untracked().and_then(|ceiling| {
+                            let model_info = cached_models
+                                .get_untracked()
+                                .into_iter()
+                                .find(|m| m.id == current_model_name.get_untracked())?;
+                            let projected_cost = cost_tracker::actual_session_cost_usd(
+                                &messages.get_untracked(),
+                            ) + cost_tracker::tally_messages(&messages_to_submit)
+                                .prompt_cost_usd(&model_info);
+                            (projected_cost > ceiling).then_some(projected_cost)
+                        });
+
+                        let over_context_limit = context_limit.get_untracked().and_then(|limit| {
+                            let projected_tokens: usize = messages_to_submit
+                                .iter()
+                                .map(|m| tokenizer::estimate_tokens(&m.content))
+                                .sum();
+                            (projected_tokens as u32 > limit).then_some((projected_tokens, limit))
+                        });
+
+                        if let Some(projected_cost) = over_budget {
+                            set_error.set(Some(format!(
+                                "Budget ceiling exceeded: this request is projected to cost ${projected_cost:.4}, over your ${:.2} limit.",
+                                state.budget_ceiling_usd.get_untracked().unwrap_or(0.0)
+                            )));
+                        } else if let Some((projected_tokens, limit)) = over_context_limit {
+                            set_error.set(Some(format!(
+                                "This request is projected to use ~{projected_tokens} tokens, over {}'s {limit}-token context window.",
+                                current_model_name.get_untracked()
+                            )));
+                        } else {
+                            handle_llm_request(
+                                messages_to_submit,
+                                model,
+                                api_key.get(),
+                                set_messages,
+                                set_error,
+                                cached_models,
+                                current_model_name.get(),
+                                mentioned_name,
+                                mentioned_body,
+                                state.record_usage,
+                                rx,
+                            )
+                            .await;
+                        }
+                    }
+                    Err(e) => {
+                        set_error.set(Some(format!("Failed to expand system prompt: {e}")));
+                    }
                 }
-                messages_to_submit.extend(messages.get());
-
-                handle_llm_request(
-                    messages_to_submit,
-                    model,
-                    api_key.get(),
-                    set_messages,
-                    set_error,
-                    cached_models,
-                    current_model_name.get(),
-                    selected_prompt,
-                    rx,
-                )
-                .await;
             }
 
             set_input_disabled.set(false);
@@ -267,6 +580,10 @@ pub fn ChatInterface(
                 model: current_model_name(),
                 seed: None,
                 temperature: Some(1.0),
+                provider: state.provider_kind.get_untracked(),
+                tools: Vec::new(),
+                max_completion_tokens: None,
+                timeout_secs: None,
             };
             spawn_local(async move {
                 let (tx, rx) = oneshot::channel();
@@ -274,43 +591,160 @@ pub fn ChatInterface(
                 set_input_disabled.set(true);
                 set_error(None);
 
+                let old_message = messages.get_untracked().get(index).cloned();
+                let parent_id = index
+                    .checked_sub(1)
+                    .and_then(|i| messages.get_untracked().get(i).map(|m| m.id.clone()));
                 set_messages.update(|m| {
                     m.drain(index..);
                 });
 
-                let system_prompt_content = selected_prompt()
-                    .map(|sp| sp.prompt)
-                    .unwrap_or("".to_string());
+                // No freshly-typed text to scan at regenerate time, so mentions
+                // are recovered from the user message this reply answers
+                // (falling back to the single `selected_prompt`, same as
+                // `submit` does when nothing was mentioned).
+                let mentioning_content = parent_id
+                    .as_ref()
+                    .and_then(|id| messages.get_untracked().iter().find(|m| &m.id == id).cloned())
+                    .filter(|m| m.role == "user")
+                    .map(|m| m.content);
+                let mentions = {
+                    let from_input = mentioning_content
+                        .map(|content| extract_mentions(&content, &system_prompts()))
+                        .unwrap_or_default();
+                    if from_input.is_empty() {
+                        selected_prompt
+                            .get_untracked()
+                            .map(|sp| {
+                                let vars = sp.variables.iter().cloned().collect();
+                                vec![(sp, vars)]
+                            })
+                            .unwrap_or_default()
+                    } else {
+                        from_input
+                    }
+                };
 
                 if api_key().is_empty() {
                     set_error.set(Some(
                         "No API key provided. Please add one in Settings.".to_string(),
                     ));
                 } else {
-                    let mut messages_to_submit = Vec::new();
-                    if !system_prompt_content.is_empty() {
-                        messages_to_submit.push(Message {
-                            role: "system".to_string(),
-                            content: system_prompt_content,
-                            prompt_name: selected_prompt.get().map(|sp| sp.name.clone()),
-                            system_prompt_content: selected_prompt
-                                .get()
-                                .map(|sp| sp.prompt.clone()),
-                            model_name: Some(current_model_name()),
-                            cost: None,
-                        });
+                    let expanded_system_prompt_content = if mentions.is_empty() {
+                        Ok(String::new())
+                    } else {
+                        render_mentions(&mentions).await
+                    };
+
+                    let mentioned_name: Option<String> = (!mentions.is_empty()).then(|| {
+                        mentions.iter().map(|(sp, _)| sp.name.clone()).collect::<Vec<_>>().join(", ")
+                    });
+                    let mentioned_body: Option<String> = (!mentions.is_empty()).then(|| {
+                        mentions.iter().map(|(sp, _)| sp.prompt.clone()).collect::<Vec<_>>().join("\n\n")
+                    });
+
+                    match expanded_system_prompt_content {
+                        Ok(expanded_system_prompt_content) => {
+                            let mut messages_to_submit = Vec::new();
+                            if !expanded_system_prompt_content.is_empty() {
+                                messages_to_submit.push(Message {
+                                    id: Uuid::new_v4().to_string(),
+                                    parent_id: None,
+                                    role: "system".to_string(),
+                                    content: expanded_system_prompt_content,
+                                    prompt_name: mentioned_name.clone(),
+                                    system_prompt_content: mentioned_body.clone(),
+                                    model_name: Some(current_model_name()),
+                                    cost: None,
+                                    interrupted: false,
+                                    diff_hunks: None,
+                                    retry_status: None,
+                                });
+                            }
+                            messages_to_submit.extend(messages());
+
+                            match old_message {
+                                Some(old_message) if old_message.role == "assistant" => {
+                                    request::regenerate_llm_request(
+                                        old_message,
+                                        parent_id,
+                                        messages_to_submit,
+                                        model,
+                                        api_key(),
+                                        set_messages,
+                                        set_error,
+                                        cached_models,
+                                        current_model_name(),
+                                        mentioned_name,
+                                        mentioned_body,
+                                        state.record_usage,
+                                        rx,
+                                    )
+                                    .await;
+                                }
+                                _ => {
+                                    handle_llm_request(
+                                        messages_to_submit,
+                                        model,
+                                        api_key(),
+                                        set_messages,
+                                        set_error,
+                                        cached_models,
+                                        current_model_name(),
+                                        mentioned_name,
+                                        mentioned_body,
+                                        state.record_usage,
+                                        rx,
+                                    )
+                                    .await;
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            set_error.set(Some(format!("Failed to expand system prompt: {e}")));
+                        }
                     }
-                    messages_to_submit.extend(messages());
+                }
+
+                set_input_disabled.set(false);
+                set_cancel_sender.set(None);
+            })
+        })
+    };
 
-                    handle_llm_request(
-                        messages_to_submit,
+    let continue_generation: Arc<dyn Fn(usize) + Send + Sync> = {
+        Arc::new(move |index: usize| {
+            let model = llm::Model {
+                model: current_model_name(),
+                seed: None,
+                temperature: Some(1.0),
+                provider: state.provider_kind.get_untracked(),
+                tools: Vec::new(),
+                max_completion_tokens: None,
+                timeout_secs: None,
+            };
+            spawn_local(async move {
+                let (tx, rx) = oneshot::channel();
+                set_cancel_sender.set(Some(tx));
+                set_input_disabled.set(true);
+                set_error(None);
+
+                let messages_so_far = messages.get()[..=index].to_vec();
+
+                if api_key().is_empty() {
+                    set_error.set(Some(
+                        "No API key provided. Please add one in Settings.".to_string(),
+                    ));
+                } else {
+                    request::continue_llm_request(
+                        messages_so_far,
                         model,
                         api_key(),
                         set_messages,
                         set_error,
                         cached_models,
                         current_model_name(),
-                        selected_prompt,
+                        state.record_usage,
                         rx,
                     )
                     .await;
@@ -330,6 +764,17 @@ pub fn ChatInterface(
         });
     });
 
+    // Expose the cancel action to the global command palette while a request
+    // may be in flight.
+    Effect::new(move |_| {
+        let cancel_request = if input_disabled.get() {
+            Some(cancel_action)
+        } else {
+            None
+        };
+        state.cancel_request.set(cancel_request);
+    });
+
     let submit_for_effect = submit;
     Effect::new(move |_| {
         if let Some(prompt) = initial_chat_prompt.get() {
@@ -367,17 +812,78 @@ pub fn ChatInterface(
                     >
                         "reload"
                     </button>
+                    <label style="display:flex; align-items:center; gap:4px; font-size:0.85em; white-space:nowrap;" title="Embed prior turns and inject the most relevant ones as context">
+                        <input
+                            type="checkbox"
+                            prop:checked=retrieval_enabled
+                            on:change:target=move |ev| set_retrieval_enabled.set(ev.target().checked())
+                        />
+                        "retrieval"
+                    </label>
+                    <input
+                        type="number"
+                        min="1"
+                        max="20"
+                        style="width:3em;"
+                        disabled=move || !retrieval_enabled.get()
+                        prop:value=move || retrieval_k.get().to_string()
+                        on:input:target=move |ev| {
+                            if let Ok(k) = ev.target().value().parse::<usize>() {
+                                set_retrieval_k.set(k.max(1));
+                            }
+                        }
+                    />
                 </div>
                 {move || {
-                    selected_prompt()
+                    let retrieved = last_retrieved.get();
+                    (!retrieved.is_empty())
+                        .then(|| {
+                            view! {
+                                <details style="margin:4px; font-size:0.85em; opacity:0.8;">
+                                    <summary>
+                                        {format!("{} retrieved snippet(s) injected as context", retrieved.len())}
+                                    </summary>
+                                    <ul>
+                                        {retrieved
+                                            .into_iter()
+                                            .map(|r| {
+                                                view! {
+                                                    <li>
+                                                        {format!("(score {:.2}) {}", r.score, r.content)}
+                                                    </li>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </ul>
+                                </details>
+                            }
+                        })
+                }}
+                {move || {
+                    // Previews every prompt that would actually be sent: all
+                    // `@`-mentions in the input being typed, or the single
+                    // manually-selected prompt if nothing's mentioned yet.
+                    let from_input = extract_mentions(&input(), &system_prompts());
+                    let preview_prompts: Vec<SystemPrompt> = if from_input.is_empty() {
+                        selected_prompt().into_iter().collect()
+                    } else {
+                        from_input.into_iter().map(|(sp, _)| sp).collect()
+                    };
+                    preview_prompts
+                        .into_iter()
                         .map(|system_prompt| {
                             let system_message_for_render = Message {
+                                id: Uuid::new_v4().to_string(),
+                                parent_id: None,
                                 role: "system".to_string(),
                                 content: system_prompt.prompt,
                                 prompt_name: Some(system_prompt.name),
                                 system_prompt_content: None,
                                 model_name: None,
                                 cost: None,
+                                interrupted: false,
+                                diff_hunks: None,
+                                retry_status: None,
                             };
                             view! {
                                 <ChatMessage
@@ -388,18 +894,26 @@ pub fn ChatInterface(
                                 />
                             }
                         })
+                        .collect_view()
                 }}
                 {move || {
                     messages()
                         .into_iter()
                         .enumerate()
                         .map(|(message_index, message)| {
+                            let siblings = persistence::siblings_of(&state.all_messages.get(), &message.id)
+                                .into_iter()
+                                .map(|m| m.id.clone())
+                                .collect::<Vec<_>>();
                             view! {
                                 <ChatMessage
                                     message=message
                                     set_messages
                                     message_index
                                     regenerate=regenerate_for_messages.clone()
+                                    continue_generation=continue_generation.clone()
+                                    siblings=siblings
+                                    switch_branch=state.switch_branch
                                 />
                             }
                         })
@@ -417,6 +931,20 @@ pub fn ChatInterface(
                         })
                 }}
             </chat-history>
+            <CostTracker
+                messages=messages
+                model_name=model_name
+                cached_models=cached_models
+                pending_input=input
+                budget_ceiling_usd=state.budget_ceiling_usd
+                set_budget_ceiling_usd=state.set_budget_ceiling_usd
+            />
+            <div style="display:flex; justify-content:flex-end; padding:0 8px; font-size:0.75em; opacity:0.6;">
+                {move || format!(
+                    "${:.4} all-time",
+                    crate::usage::total_cost_usd(&state.usage.get()),
+                )}
+            </div>
             <ChatControls
                 input=input
                 set_input=set_input
@@ -424,6 +952,8 @@ pub fn ChatInterface(
                 ref_input=ref_input
                 submit=submit
                 cancel_action=cancel_action
+                token_count=token_count
+                context_limit=Signal::derive(move || context_limit.get())
             />
         </chat-interface>
     }
@@ -33,6 +33,8 @@ pub fn submit_action(
         return;
     }
 
+    let provider_kind = state.provider_kind.get_untracked();
+
     let prepare_messages = async move {
         if state.current_session_id.get().is_none() {
             let new_id = Uuid::new_v4().to_string();
@@ -41,12 +43,17 @@ pub fn submit_action(
         }
 
         let user_message = Message {
+            id: Uuid::new_v4().to_string(),
+            parent_id: messages.get_untracked().last().map(|m| m.id.clone()),
             role: "user".to_string(),
             content,
             prompt_name: None,
             system_prompt_content: None,
             model_name: None,
             cost: None,
+            interrupted: false,
+            diff_hunks: None,
+            retry_status: None,
         };
 
         set_input.set("".to_string());
@@ -69,6 +76,7 @@ pub fn submit_action(
         move || prepare_messages,
         post_hook,
         current_model_name,
+        provider_kind,
         set_cancel_sender,
         set_input_disabled,
         set_error,
@@ -80,9 +88,11 @@ pub fn submit_action(
     );
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn regenerate_action(
     index: usize,
     current_model_name: Memo<String>,
+    provider_kind: crate::provider::ProviderKind,
     set_cancel_sender: WriteSignal<Option<oneshot::Sender<()>>>,
     set_input_disabled: WriteSignal<bool>,
     set_error: WriteSignal<Option<String>>,
@@ -104,6 +114,7 @@ pub fn regenerate_action(
         move || prepare_messages,
         post_hook,
         current_model_name,
+        provider_kind,
         set_cancel_sender,
         set_input_disabled,
         set_error,
@@ -120,6 +131,7 @@ fn execute_llm_request<'a, F, Fut>(
     prepare_messages: F,
     post_hook: impl FnOnce() + 'static,
     current_model_name: Memo<String>,
+    provider_kind: crate::provider::ProviderKind,
     set_cancel_sender: WriteSignal<Option<oneshot::Sender<()>>>,
     set_input_disabled: WriteSignal<bool>,
     set_error: WriteSignal<Option<String>>,
@@ -136,6 +148,10 @@ fn execute_llm_request<'a, F, Fut>(
         model: current_model_name(),
         seed: None,
         temperature: Some(1.0),
+        provider: provider_kind,
+        tools: Vec::new(),
+        max_completion_tokens: None,
+        timeout_secs: None,
     };
 
     spawn_local(async move {
@@ -159,6 +175,8 @@ fn execute_llm_request<'a, F, Fut>(
             let mut messages_to_submit = Vec::new();
             if !system_prompt_content.is_empty() {
                 messages_to_submit.push(Message {
+                    id: Uuid::new_v4().to_string(),
+                    parent_id: None,
                     role: "system".to_string(),
                     content: system_prompt_content,
                     prompt_name: selected_prompt.get().map(|sp| sp.name.clone()),
@@ -167,6 +185,9 @@ fn execute_llm_request<'a, F, Fut>(
                         .map(|sp| sp.prompt.clone()),
                     model_name: Some(current_model_name()),
                     cost: None,
+                        interrupted: false,
+                    diff_hunks: None,
+                    retry_status: None,
                 });
             }
             messages_to_submit.extend(messages());
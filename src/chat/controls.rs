@@ -10,9 +10,31 @@ pub fn ChatControls(
     #[prop(into)] submit: Callback<Option<String>>,
     #[prop(into)] cancel_action: Callback<()>,
     #[prop(into)] is_mobile: Signal<bool>,
+    /// Estimated token count of the system prompt + history + current
+    /// input, from `tokenizer::estimate_tokens`. See `ChatInterface` for how
+    /// it's kept up to date.
+    #[prop(into)] token_count: Signal<usize>,
+    /// The selected model's max context length, if `DisplayModelInfo`
+    /// reported one.
+    #[prop(into)] context_limit: Signal<Option<u32>>,
 ) -> impl IntoView {
+    let near_limit = move || {
+        context_limit
+            .get()
+            .is_some_and(|limit| limit > 0 && token_count.get() as f64 / limit as f64 >= 0.9)
+    };
+
     view! {
         <chat-controls>
+            <token-budget
+                data-role=move || if near_limit() { "destructive" } else { "" }
+                style="display:flex; justify-content:flex-end; padding:0 8px; font-size:0.75em; opacity:0.7;"
+            >
+                {move || match context_limit.get() {
+                    Some(limit) => format!("~{} / {} tokens", token_count.get(), limit),
+                    None => format!("~{} tokens", token_count.get()),
+                }}
+            </token-budget>
             <form on:submit=move |ev| {
                 ev.prevent_default();
                 if !input_disabled.get() {
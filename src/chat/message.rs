@@ -1,4 +1,5 @@
 use crate::copy_button::CopyButton;
+use crate::diff::Hunk;
 use crate::markdown::Markdown;
 use leptos::ev::KeyboardEvent;
 use leptos::prelude::*;
@@ -6,12 +7,52 @@ use std::sync::Arc;
 
 use super::types::Message;
 
+/// Renders a live regenerate/edit diff: kept text plain, inserted text
+/// highlighted, deleted text struck through. `old_text` is the message
+/// content as it stood before this regeneration started (unchanged for the
+/// duration of the diff, so hunk offsets into it stay valid).
+fn render_diff(old_text: &str, hunks: &[Hunk]) -> impl IntoView {
+    let old_chars: Vec<char> = old_text.chars().collect();
+    let mut cursor = 0usize;
+    hunks
+        .iter()
+        .map(|hunk| match hunk {
+            Hunk::Keep(n) => {
+                let text: String = old_chars[cursor..cursor + n].iter().collect();
+                cursor += n;
+                view! { <span>{text}</span> }.into_any()
+            }
+            Hunk::Delete(n) => {
+                let text: String = old_chars[cursor..cursor + n].iter().collect();
+                cursor += n;
+                view! { <del style="opacity:0.6;">{text}</del> }.into_any()
+            }
+            Hunk::Insert(text) => {
+                view! {
+                    <ins style="background:var(--highlight-color, #2a4); text-decoration:none;">
+                        {text.clone()}
+                    </ins>
+                }
+                    .into_any()
+            }
+        })
+        .collect_view()
+}
+
 #[component]
 pub fn ChatMessage(
     #[prop(into)] message: Message,
     #[prop(into)] set_messages: WriteSignal<Vec<Message>>,
     #[prop(into)] message_index: usize,
     regenerate: Arc<impl Fn(usize) + std::marker::Send + std::marker::Sync + 'static>,
+    #[prop(optional)] continue_generation: Option<Arc<dyn Fn(usize) + Send + Sync>>,
+    /// Ids of every alternate of this message (itself included, see
+    /// `persistence::siblings_of`), for the "‹ i/n ›" switcher. `None` or a
+    /// single-element list hides the switcher.
+    #[prop(optional)] siblings: Option<Vec<String>>,
+    /// Switches the active conversation to the branch containing the given
+    /// message id. Required for the switcher to render at all.
+    #[prop(optional)] switch_branch: Option<Callback<String>>,
 ) -> impl IntoView {
     let (is_editing, set_is_editing) = signal(false);
     let (input, set_input) = signal(message.content.clone());
@@ -65,13 +106,81 @@ pub fn ChatMessage(
                     }}
                 </chat-message-role>
                 <chat-message-buttons>
+                    {
+                        let siblings = siblings.clone();
+                        let message_id = message.id.clone();
+                        move || {
+                            let (Some(siblings), Some(switch_branch)) = (siblings.clone(), switch_branch)
+                            else {
+                                return ().into_any();
+                            };
+                            if siblings.len() < 2 {
+                                return ().into_any();
+                            }
+                            let current = siblings.iter().position(|id| *id == message_id).unwrap_or(0);
+                            let prev_id = (current > 0).then(|| siblings[current - 1].clone());
+                            let next_id = siblings.get(current + 1).cloned();
+                            view! {
+                                <chat-message-branch-switcher style="display:flex; align-items:center; gap:2px;">
+                                    <button
+                                        data-size="compact"
+                                        disabled=prev_id.is_none()
+                                        on:click=move |_| {
+                                            if let Some(id) = prev_id.clone() {
+                                                switch_branch.run(id);
+                                            }
+                                        }
+                                    >
+                                        "‹"
+                                    </button>
+                                    <span style="font-size:0.8em; opacity:0.7;">
+                                        {format!("{}/{}", current + 1, siblings.len())}
+                                    </span>
+                                    <button
+                                        data-size="compact"
+                                        disabled=next_id.is_none()
+                                        on:click=move |_| {
+                                            if let Some(id) = next_id.clone() {
+                                                switch_branch.run(id);
+                                            }
+                                        }
+                                    >
+                                        "›"
+                                    </button>
+                                </chat-message-branch-switcher>
+                            }
+                                .into_any()
+                        }
+                    }
                     <CopyButton text_to_copy=text_for_copy_button />
                     {
                         let regenerate = regenerate.clone();
+                        let continue_generation = continue_generation.clone();
                         let message = message.clone();
                         move || {
                             let regenerate = regenerate.clone();
-                            if message.role.clone() == "assistant" {
+                            if message.role.clone() == "assistant" && message.interrupted {
+                                let continue_generation = continue_generation.clone();
+                                view! {
+                                    <button
+                                        data-size="compact"
+                                        on:click=move |_| {
+                                            if let Some(continue_generation) = &continue_generation {
+                                                continue_generation(message_index)
+                                            }
+                                        }
+                                    >
+                                        "continue"
+                                    </button>
+                                    <button
+                                        data-size="compact"
+                                        on:click=move |_| { regenerate(message_index) }
+                                    >
+                                        "regenerate"
+                                    </button>
+                                }
+                                    .into_any()
+                            } else if message.role.clone() == "assistant" {
                                 view! {
                                     <button
                                         data-size="compact"
@@ -137,12 +246,26 @@ pub fn ChatMessage(
                             </div>
                         }
                             .into_any()
+                    } else if let Some(hunks) = &message.diff_hunks {
+                        render_diff(&message.content, hunks).into_any()
                     } else {
                         let content = message.content.clone();
                         view! { <Markdown markdown_text=content /> }.into_any()
                     }
                 }}
             </chat-message-content>
+            {
+                let retry_status = message.retry_status.clone();
+                move || {
+                    retry_status.clone().map(|status| {
+                        view! {
+                            <chat-message-retry-status style="font-size: 0.8em; opacity: 0.6;">
+                                {status}
+                            </chat-message-retry-status>
+                        }
+                    })
+                }
+            }
             {move || {
                 message_for_cost
                     .cost
@@ -1,10 +1,345 @@
-use super::types::{Message, MessageCost, SystemPrompt};
+use super::types::{new_message_id, Message, MessageCost};
+use crate::diff::StreamingDiff;
 use crate::llm::{self, DisplayModelInfo, StreamedMessage};
+use crate::notifications::{self, StreamOutcome};
 use futures::{pin_mut, select, FutureExt, StreamExt};
 use futures_channel::oneshot;
 use leptos::logging::log;
 use leptos::prelude::*;
 
+/// Shortens `text` to a notification-friendly snippet.
+fn notification_snippet(text: &str) -> String {
+    const MAX_LEN: usize = 140;
+    let trimmed = text.trim();
+    if trimmed.chars().count() <= MAX_LEN {
+        trimmed.to_string()
+    } else {
+        let snippet: String = trimmed.chars().take(MAX_LEN).collect();
+        format!("{snippet}…")
+    }
+}
+
+/// Backoff schedule for reconnecting a dropped stream. `base_delay_ms`
+/// doubles with each attempt (capped) and gets up to 25% jitter added so
+/// concurrently-retrying tabs don't all hammer the API at once.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay_ms: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay_ms: 500,
+        }
+    }
+}
+
+/// Classifies an error from `llm::request_message_content_streamed` (or a
+/// mid-stream chunk) as worth retrying. There's no structured error type to
+/// match on, so this is a best-effort read of the message text: auth and
+/// malformed-request failures won't succeed on retry and should fail fast,
+/// everything else (network drops, 429/503, timeouts) is assumed transient.
+fn is_retryable_error(err: &anyhow::Error) -> bool {
+    let message = err.to_string().to_lowercase();
+    let non_retryable = [
+        "401",
+        "403",
+        "unauthorized",
+        "forbidden",
+        "api key",
+        "invalid request",
+    ];
+    !non_retryable.iter().any(|needle| message.contains(needle))
+}
+
+/// Exponential backoff with jitter for retry attempt `attempt` (1-indexed).
+fn backoff_delay_ms(attempt: u32, base_delay_ms: u32) -> u32 {
+    let exponential = base_delay_ms.saturating_mul(1u32 << attempt.min(6).saturating_sub(1));
+    let jitter = (web_sys::js_sys::Math::random() * exponential as f64 * 0.25) as u32;
+    exponential + jitter
+}
+
+/// Waits out `ms` milliseconds, or returns early with `true` if `cancel_receiver`
+/// fires first (so a retry backoff doesn't block cancellation).
+async fn sleep_cancelable(ms: u32, cancel_receiver: &mut oneshot::Receiver<()>) -> bool {
+    let timeout = gloo_timers::future::TimeoutFuture::new(ms);
+    select! {
+        _ = cancel_receiver.fuse() => true,
+        _ = timeout.fuse() => false,
+    }
+}
+
+/// Connects (or reconnects) the stream and drives it to completion, patching
+/// the last message in `set_messages` as content arrives. `accumulated_content`
+/// is whatever the last message already holds (empty for a fresh request, the
+/// prior partial text for a resumed one) and is threaded through retries so a
+/// reconnect resumes rather than restarts. On a retryable connect or
+/// mid-stream error, this backs off (respecting `cancel_receiver`) and
+/// reconnects, up to `retry_policy.max_attempts`, with `messages_to_submit`
+/// plus (once this call has received any text) that text as an assistant
+/// turn and a continuation nudge.
+#[allow(clippy::too_many_arguments)]
+async fn stream_into_last_message(
+    messages_to_submit: Vec<llm::Message>,
+    model: llm::Model,
+    api_key: String,
+    mut accumulated_content: String,
+    mut cancel_receiver: oneshot::Receiver<()>,
+    set_messages: WriteSignal<Vec<Message>>,
+    set_error: WriteSignal<Option<String>>,
+    cached_models: Signal<Vec<DisplayModelInfo>>,
+    current_model_name: String,
+    retry_policy: RetryPolicy,
+    /// Fired once per settled `Usage` chunk, so `GlobalState::usage` (and
+    /// the persisted rollup behind it) stay in step with the per-message
+    /// `cost` this same chunk just wrote. See `usage::UsageSummary`.
+    record_usage: Callback<(String, llm::Usage, MessageCost)>,
+) {
+    let mut attempt = 0u32;
+    let resume_from = accumulated_content.len();
+
+    'reconnect: loop {
+        let mut request_messages = messages_to_submit.clone();
+        if accumulated_content.len() > resume_from {
+            request_messages.push(llm::Message {
+                role: "assistant".to_string(),
+                content: accumulated_content[resume_from..].to_string(),
+            });
+            request_messages.push(llm::Message {
+                role: "user".to_string(),
+                content: "Continue your previous response exactly where it left off.".to_string(),
+            });
+        }
+        let stream = match llm::request_message_content_streamed(
+            request_messages,
+            model.clone(),
+            api_key.clone(),
+        )
+        .await
+        {
+            Ok(stream) => stream,
+            Err(err) => {
+                if attempt < retry_policy.max_attempts && is_retryable_error(&err) {
+                    attempt += 1;
+                    log!(
+                        "[WARN] [LLM] Stream connect failed (attempt {attempt}/{}): {err}. Retrying...",
+                        retry_policy.max_attempts
+                    );
+                    set_messages.update(|m| {
+                        if let Some(last) = m.last_mut() {
+                            last.retry_status =
+                                Some(format!("retrying ({attempt}/{})…", retry_policy.max_attempts));
+                        }
+                    });
+                    let delay = backoff_delay_ms(attempt, retry_policy.base_delay_ms);
+                    if sleep_cancelable(delay, &mut cancel_receiver).await {
+                        set_messages.update(|m| {
+                            if let Some(last) = m.last_mut() {
+                                last.retry_status = None;
+                                last.interrupted = true;
+                            }
+                        });
+                        notifications::notify_if_hidden(
+                            &current_model_name,
+                            StreamOutcome::Cancelled,
+                            &notification_snippet(&accumulated_content),
+                        );
+                        return;
+                    }
+                    continue 'reconnect;
+                }
+                set_error.set(Some(err.to_string()));
+                set_messages.update(|m| {
+                    if let Some(last) = m.last_mut() {
+                        last.content = accumulated_content.clone();
+                        last.retry_status = None;
+                        last.interrupted = true;
+                    }
+                });
+                notifications::notify_if_hidden(
+                    &current_model_name,
+                    StreamOutcome::Error,
+                    &notification_snippet(&accumulated_content),
+                );
+                return;
+            }
+        };
+        set_messages.update(|m| {
+            if let Some(last) = m.last_mut() {
+                last.retry_status = None;
+            }
+        });
+        pin_mut!(stream);
+
+        let mut buffer = String::new();
+        let mut last_update_time: Option<f64> = None;
+        const THROTTLE_MS: f64 = 200.0;
+        let performance = window()
+            .performance()
+            .expect("performance should be available");
+
+        loop {
+            select! {
+                _ = cancel_receiver => {
+                    log!("[INFO] LLM request cancelled by user.");
+                    if !buffer.is_empty() {
+                        accumulated_content.push_str(&buffer);
+                        buffer.clear();
+                    }
+                    set_messages.update(|m| {
+                        if let Some(last) = m.last_mut() {
+                            last.content = accumulated_content.clone();
+                            last.interrupted = true;
+                        }
+                    });
+                    notifications::notify_if_hidden(
+                        &current_model_name,
+                        StreamOutcome::Cancelled,
+                        &notification_snippet(&accumulated_content),
+                    );
+                    return;
+                },
+                chunk_result = stream.next().fuse() => {
+                    if let Some(chunk_result) = chunk_result {
+                         match chunk_result {
+                            Ok(streamed_message) => match streamed_message {
+                                StreamedMessage::Content(content) => {
+                                    buffer.push_str(&content);
+                                    let now = performance.now();
+                                    let should_update = if let Some(last_time) = last_update_time {
+                                        now - last_time > THROTTLE_MS
+                                    } else {
+                                        true // First chunk, update immediately
+                                    };
+
+                                    if should_update {
+                                        accumulated_content.push_str(&buffer);
+                                        buffer.clear();
+                                        set_messages.update(|m| {
+                                            if let Some(last) = m.last_mut() {
+                                                last.content = accumulated_content.clone();
+                                            }
+                                        });
+                                        last_update_time = Some(now);
+                                    }
+                                }
+                                StreamedMessage::ToolCall { id, name, arguments } => {
+                                    // No agent loop wired up yet; log so tool
+                                    // calls are visible instead of silently
+                                    // vanishing.
+                                    log!(
+                                        "[INFO] [LLM] Model requested tool call {name} (id={id}): {arguments}"
+                                    );
+                                }
+                                StreamedMessage::Usage(usage) => {
+                                    if !buffer.is_empty() {
+                                        accumulated_content.push_str(&buffer);
+                                        buffer.clear();
+                                        set_messages.update(|m| {
+                                            if let Some(last) = m.last_mut() {
+                                                last.content = accumulated_content.clone();
+                                            }
+                                        });
+                                    }
+
+                                    let model_info = cached_models
+                                        .get()
+                                        .into_iter()
+                                        .find(|m| m.id == current_model_name);
+                                    if let Some(model_info) = model_info {
+                                        let cost = MessageCost::from_usage(usage, &model_info);
+                                        set_messages.update(|m| {
+                                            if let Some(last) = m.last_mut() {
+                                                last.cost = Some(cost);
+                                            }
+                                        });
+                                        record_usage.run((current_model_name.clone(), usage, cost));
+                                    }
+                                }
+                            },
+                            Err(err) => {
+                                if !buffer.is_empty() {
+                                    accumulated_content.push_str(&buffer);
+                                    buffer.clear();
+                                }
+                                if attempt < retry_policy.max_attempts && is_retryable_error(&err) {
+                                    attempt += 1;
+                                    log!(
+                                        "[WARN] [LLM] Stream dropped (attempt {attempt}/{}): {err}. Retrying...",
+                                        retry_policy.max_attempts
+                                    );
+                                    set_messages.update(|m| {
+                                        if let Some(last) = m.last_mut() {
+                                            last.content = accumulated_content.clone();
+                                            last.retry_status = Some(format!(
+                                                "retrying ({attempt}/{})…",
+                                                retry_policy.max_attempts
+                                            ));
+                                        }
+                                    });
+                                    let delay = backoff_delay_ms(attempt, retry_policy.base_delay_ms);
+                                    if sleep_cancelable(delay, &mut cancel_receiver).await {
+                                        set_messages.update(|m| {
+                                            if let Some(last) = m.last_mut() {
+                                                last.retry_status = None;
+                                                last.interrupted = true;
+                                            }
+                                        });
+                                        notifications::notify_if_hidden(
+                                            &current_model_name,
+                                            StreamOutcome::Cancelled,
+                                            &notification_snippet(&accumulated_content),
+                                        );
+                                        return;
+                                    }
+                                    continue 'reconnect;
+                                }
+                                set_error.set(Some(err.to_string()));
+                                set_messages.update(|m| {
+                                    if let Some(last) = m.last_mut() {
+                                        last.content = accumulated_content.clone();
+                                        last.retry_status = None;
+                                        last.interrupted = true;
+                                    }
+                                });
+                                notifications::notify_if_hidden(
+                                    &current_model_name,
+                                    StreamOutcome::Error,
+                                    &notification_snippet(&accumulated_content),
+                                );
+                                return;
+                            }
+                        }
+                    } else {
+                        // Stream finished
+                        break;
+                    }
+                }
+            }
+        }
+
+        if !buffer.is_empty() {
+            accumulated_content.push_str(&buffer);
+            set_messages.update(|m| {
+                if let Some(last) = m.last_mut() {
+                    last.content = accumulated_content.clone();
+                }
+            });
+        }
+
+        notifications::notify_if_hidden(
+            &current_model_name,
+            StreamOutcome::Completed,
+            &notification_snippet(&accumulated_content),
+        );
+        return;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn handle_llm_request(
     messages_to_submit: Vec<Message>,
     model: llm::Model,
@@ -13,136 +348,329 @@ pub async fn handle_llm_request(
     set_error: WriteSignal<Option<String>>,
     cached_models: Signal<Vec<DisplayModelInfo>>,
     current_model_name: String,
-    selected_prompt: Memo<Option<SystemPrompt>>,
-    mut cancel_receiver: oneshot::Receiver<()>,
+    /// Joined name(s)/body(ies) of whichever prompt(s) were mentioned for
+    /// this request — see `chat::extract_mentions` — so the assistant
+    /// reply can be tagged with what produced it.
+    prompt_name: Option<String>,
+    system_prompt_content: Option<String>,
+    record_usage: Callback<(String, llm::Usage, MessageCost)>,
+    cancel_receiver: oneshot::Receiver<()>,
 ) {
     let response_message = Message {
+        id: new_message_id(),
+        parent_id: messages_to_submit.last().map(|m| m.id.clone()),
         role: "assistant".to_string(),
         content: String::new(),
-        prompt_name: selected_prompt.get().map(|sp| sp.name.clone()),
-        system_prompt_content: selected_prompt.get().map(|sp| sp.prompt.clone()),
+        prompt_name,
+        system_prompt_content,
         model_name: Some(current_model_name.clone()),
         cost: None,
+        interrupted: false,
+        diff_hunks: None,
+        retry_status: None,
     };
     set_messages.update(|m| m.push(response_message));
 
-    match llm::request_message_content_streamed(
+    stream_into_last_message(
+        messages_to_submit.iter().map(|m| m.to_llm()).collect(),
+        model,
+        api_key,
+        String::new(),
+        cancel_receiver,
+        set_messages,
+        set_error,
+        cached_models,
+        current_model_name,
+        RetryPolicy::default(),
+        record_usage,
+    )
+    .await;
+}
+
+/// Resumes an assistant message that was previously cut short by
+/// cancellation (`message.interrupted == true`). Re-submits the conversation
+/// up to and including the partial message plus a short continuation nudge,
+/// then keeps streaming new content onto the *same* message rather than
+/// appending a new one.
+#[allow(clippy::too_many_arguments)]
+pub async fn continue_llm_request(
+    messages_so_far: Vec<Message>,
+    model: llm::Model,
+    api_key: String,
+    set_messages: WriteSignal<Vec<Message>>,
+    set_error: WriteSignal<Option<String>>,
+    cached_models: Signal<Vec<DisplayModelInfo>>,
+    current_model_name: String,
+    record_usage: Callback<(String, llm::Usage, MessageCost)>,
+    cancel_receiver: oneshot::Receiver<()>,
+) {
+    let Some(partial_content) = messages_so_far.last().map(|m| m.content.clone()) else {
+        return;
+    };
+
+    set_messages.update(|m| {
+        if let Some(last) = m.last_mut() {
+            last.interrupted = false;
+        }
+    });
+
+    let mut messages_to_submit = messages_so_far;
+    let parent_id = messages_to_submit.last().map(|m| m.id.clone());
+    messages_to_submit.push(Message {
+        id: new_message_id(),
+        parent_id,
+        role: "user".to_string(),
+        content: "Continue your previous response exactly where it left off.".to_string(),
+        prompt_name: None,
+        system_prompt_content: None,
+        model_name: None,
+        cost: None,
+        interrupted: false,
+        diff_hunks: None,
+        retry_status: None,
+    });
+
+    stream_into_last_message(
         messages_to_submit.iter().map(|m| m.to_llm()).collect(),
         model,
         api_key,
+        partial_content,
+        cancel_receiver,
+        set_messages,
+        set_error,
+        cached_models,
+        current_model_name,
+        RetryPolicy::default(),
+        record_usage,
     )
-    .await
-    {
-        Ok(stream) => {
-            let mut accumulated_content = String::new();
-            pin_mut!(stream);
-
-            let mut buffer = String::new();
-            let mut last_update_time: Option<f64> = None;
-            const THROTTLE_MS: f64 = 200.0;
-            let performance = window()
-                .performance()
-                .expect("performance should be available");
-
-            loop {
-                select! {
-                    _ = cancel_receiver => {
-                        log!("[INFO] LLM request cancelled by user.");
+    .await;
+}
+
+/// Regenerates an assistant message in place: keeps `old_message` visible
+/// and streams the replacement as a live diff against it (`message.diff_hunks`)
+/// rather than popping the old text and starting from a blank message.
+#[allow(clippy::too_many_arguments)]
+pub async fn regenerate_llm_request(
+    old_message: Message,
+    parent_id: Option<String>,
+    messages_to_submit: Vec<Message>,
+    model: llm::Model,
+    api_key: String,
+    set_messages: WriteSignal<Vec<Message>>,
+    set_error: WriteSignal<Option<String>>,
+    cached_models: Signal<Vec<DisplayModelInfo>>,
+    current_model_name: String,
+    prompt_name: Option<String>,
+    system_prompt_content: Option<String>,
+    record_usage: Callback<(String, llm::Usage, MessageCost)>,
+    mut cancel_receiver: oneshot::Receiver<()>,
+) {
+    let mut diff_engine = StreamingDiff::new(&old_message.content);
+    let response_message = Message {
+        id: new_message_id(),
+        parent_id,
+        role: "assistant".to_string(),
+        content: old_message.content.clone(),
+        prompt_name,
+        system_prompt_content,
+        model_name: Some(current_model_name.clone()),
+        cost: None,
+        interrupted: false,
+        // `StreamingDiff` already accounts for the full old text on its own
+        // (its alignment starts from an empty new text against `old`), so
+        // this starts with no hunks at all rather than a seeded
+        // `Hunk::Keep(old_len)` placeholder — seeding one here double-counts
+        // `old_len` characters once the engine's own hunks are appended.
+        // `ChatMessage` renders plain `message.content` (== the old text)
+        // while this is `None`, so nothing is lost visually.
+        diff_hunks: None,
+        retry_status: None,
+    };
+    set_messages.update(|m| m.push(response_message));
+
+    let retry_policy = RetryPolicy::default();
+    let llm_messages: Vec<llm::Message> = messages_to_submit.iter().map(|m| m.to_llm()).collect();
+    let mut attempt = 0u32;
+    let mut new_content = String::new();
+
+    'reconnect: loop {
+        let stream = match llm::request_message_content_streamed(
+            llm_messages.clone(),
+            model.clone(),
+            api_key.clone(),
+        )
+        .await
+        {
+            Ok(stream) => stream,
+            Err(err) => {
+                if attempt < retry_policy.max_attempts && is_retryable_error(&err) {
+                    attempt += 1;
+                    log!(
+                        "[WARN] [LLM] Regeneration connect failed (attempt {attempt}/{}): {err}. Retrying...",
+                        retry_policy.max_attempts
+                    );
+                    set_messages.update(|m| {
+                        if let Some(last) = m.last_mut() {
+                            last.retry_status =
+                                Some(format!("retrying ({attempt}/{})…", retry_policy.max_attempts));
+                        }
+                    });
+                    let delay = backoff_delay_ms(attempt, retry_policy.base_delay_ms);
+                    if sleep_cancelable(delay, &mut cancel_receiver).await {
                         set_messages.update(|m| {
-                            m.pop(); // Remove the empty/partial assistant message
+                            if let Some(last) = m.last_mut() {
+                                last.retry_status = None;
+                                last.diff_hunks = None;
+                                last.interrupted = true;
+                            }
                         });
+                        notifications::notify_if_hidden(
+                            &current_model_name,
+                            StreamOutcome::Cancelled,
+                            &notification_snippet(&new_content),
+                        );
                         return;
-                    },
-                    chunk_result = stream.next().fuse() => {
-                        if let Some(chunk_result) = chunk_result {
-                             match chunk_result {
-                                Ok(streamed_message) => match streamed_message {
-                                    StreamedMessage::Content(content) => {
-                                        buffer.push_str(&content);
-                                        let now = performance.now();
-                                        let should_update = if let Some(last_time) = last_update_time {
-                                            now - last_time > THROTTLE_MS
-                                        } else {
-                                            true // First chunk, update immediately
-                                        };
-
-                                        if should_update {
-                                            accumulated_content.push_str(&buffer);
-                                            buffer.clear();
-                                            set_messages.update(|m| {
-                                                if let Some(last) = m.last_mut() {
-                                                    last.content = accumulated_content.clone();
-                                                }
-                                            });
-                                            last_update_time = Some(now);
-                                        }
-                                    }
-                                    StreamedMessage::Usage(usage) => {
-                                        if !buffer.is_empty() {
-                                            accumulated_content.push_str(&buffer);
-                                            buffer.clear();
-                                            set_messages.update(|m| {
-                                                if let Some(last) = m.last_mut() {
-                                                    last.content = accumulated_content.clone();
-                                                }
-                                            });
-                                        }
+                    }
+                    continue 'reconnect;
+                }
+                set_error.set(Some(err.to_string()));
+                set_messages.update(|m| {
+                    if let Some(last) = m.last_mut() {
+                        last.retry_status = None;
+                        last.diff_hunks = None;
+                        last.interrupted = true;
+                    }
+                });
+                return;
+            }
+        };
+        set_messages.update(|m| {
+            if let Some(last) = m.last_mut() {
+                last.retry_status = None;
+            }
+        });
+        pin_mut!(stream);
 
-                                        let model_info = cached_models
-                                            .get()
-                                            .into_iter()
-                                            .find(|m| m.id == current_model_name);
-                                        if let Some(model_info) = model_info {
-                                            let prompt_cost =
-                                                model_info.prompt_cost_usd_pm.unwrap_or(0.0)
-                                                    * usage.prompt_tokens as f64
-                                                    / 1_000_000.0;
-                                            let completion_cost = model_info
-                                                .completion_cost_usd_pm
-                                                .unwrap_or(0.0)
-                                                * usage.completion_tokens as f64
-                                                / 1_000_000.0;
-                                            set_messages.update(|m| {
-                                                if let Some(last) = m.last_mut() {
-                                                    last.cost = Some(MessageCost {
-                                                        prompt: prompt_cost,
-                                                        completion: completion_cost,
-                                                    });
-                                                }
-                                            });
-                                        }
+        loop {
+            select! {
+                _ = cancel_receiver => {
+                    log!("[INFO] LLM regeneration cancelled by user.");
+                    set_messages.update(|m| {
+                        if let Some(last) = m.last_mut() {
+                            last.content = new_content.clone();
+                            last.diff_hunks = None;
+                            last.interrupted = true;
+                        }
+                    });
+                    notifications::notify_if_hidden(
+                        &current_model_name,
+                        StreamOutcome::Cancelled,
+                        &notification_snippet(&new_content),
+                    );
+                    return;
+                },
+                chunk_result = stream.next().fuse() => {
+                    match chunk_result {
+                        Some(Ok(StreamedMessage::Content(content))) => {
+                            new_content.push_str(&content);
+                            let fresh_hunks = diff_engine.push(&content);
+                            if !fresh_hunks.is_empty() {
+                                set_messages.update(|m| {
+                                    if let Some(last) = m.last_mut() {
+                                        last.diff_hunks.get_or_insert_with(Vec::new).extend(fresh_hunks);
                                     }
-                                },
-                                Err(err) => {
-                                    set_error.set(Some(err.to_string()));
+                                });
+                            }
+                        }
+                        Some(Ok(StreamedMessage::ToolCall { id, name, arguments })) => {
+                            // No agent loop wired up yet; log so tool calls
+                            // are visible instead of silently vanishing.
+                            log!(
+                                "[INFO] [LLM] Model requested tool call {name} (id={id}): {arguments}"
+                            );
+                        }
+                        Some(Ok(StreamedMessage::Usage(usage))) => {
+                            let model_info = cached_models
+                                .get()
+                                .into_iter()
+                                .find(|m| m.id == current_model_name);
+                            if let Some(model_info) = model_info {
+                                let cost = MessageCost::from_usage(usage, &model_info);
+                                set_messages.update(|m| {
+                                    if let Some(last) = m.last_mut() {
+                                        last.cost = Some(cost);
+                                    }
+                                });
+                                record_usage.run((current_model_name.clone(), usage, cost));
+                            }
+                        }
+                        Some(Err(err)) => {
+                            if attempt < retry_policy.max_attempts && is_retryable_error(&err) {
+                                attempt += 1;
+                                log!(
+                                    "[WARN] [LLM] Regeneration stream dropped (attempt {attempt}/{}): {err}. Retrying...",
+                                    retry_policy.max_attempts
+                                );
+                                set_messages.update(|m| {
+                                    if let Some(last) = m.last_mut() {
+                                        last.retry_status = Some(format!(
+                                            "retrying ({attempt}/{})…",
+                                            retry_policy.max_attempts
+                                        ));
+                                    }
+                                });
+                                let delay = backoff_delay_ms(attempt, retry_policy.base_delay_ms);
+                                if sleep_cancelable(delay, &mut cancel_receiver).await {
                                     set_messages.update(|m| {
-                                        m.pop();
+                                        if let Some(last) = m.last_mut() {
+                                            last.retry_status = None;
+                                            last.diff_hunks = None;
+                                            last.interrupted = true;
+                                        }
                                     });
+                                    notifications::notify_if_hidden(
+                                        &current_model_name,
+                                        StreamOutcome::Cancelled,
+                                        &notification_snippet(&new_content),
+                                    );
                                     return;
                                 }
+                                continue 'reconnect;
                             }
-                        } else {
-                            // Stream finished
-                            break;
+                            set_error.set(Some(err.to_string()));
+                            set_messages.update(|m| {
+                                if let Some(last) = m.last_mut() {
+                                    last.content = new_content.clone();
+                                    last.retry_status = None;
+                                    last.diff_hunks = None;
+                                    last.interrupted = true;
+                                }
+                            });
+                            notifications::notify_if_hidden(
+                                &current_model_name,
+                                StreamOutcome::Error,
+                                &notification_snippet(&new_content),
+                            );
+                            return;
+                        }
+                        None => {
+                            diff_engine.finish();
+                            set_messages.update(|m| {
+                                if let Some(last) = m.last_mut() {
+                                    last.content = new_content.clone();
+                                    last.diff_hunks = None;
+                                }
+                            });
+                            notifications::notify_if_hidden(
+                                &current_model_name,
+                                StreamOutcome::Completed,
+                                &notification_snippet(&new_content),
+                            );
+                            return;
                         }
                     }
                 }
             }
-
-            if !buffer.is_empty() {
-                accumulated_content.push_str(&buffer);
-                set_messages.update(|m| {
-                    if let Some(last) = m.last_mut() {
-                        last.content = accumulated_content.clone();
-                    }
-                });
-            }
-        }
-        Err(err) => {
-            set_error.set(Some(err.to_string()));
-            set_messages.update(|m| {
-                m.pop();
-            });
         }
     }
 }
@@ -7,21 +7,29 @@ pub fn SystemPromptBar(
     #[prop(into)] system_prompts: Signal<Vec<SystemPrompt>>,
     #[prop(into)] selected_prompt_name: Signal<Option<String>>,
     #[prop(into)] set_selected_prompt_name: WriteSignal<Option<String>>,
+    /// Names of every prompt currently `@`-mentioned in the composer (see
+    /// `chat::mentioned_prompt_names`), so a button can be highlighted even
+    /// when it isn't the single `selected_prompt_name`. Defaults to empty
+    /// for callers (e.g. tests, if any existed) that don't track mentions.
+    #[prop(into, default = Signal::derive(Vec::new))] active_prompt_names: Signal<Vec<String>>,
 ) -> impl IntoView {
     view! {
         {move || {
             let selected_prompt_name = selected_prompt_name();
+            let active_prompt_names = active_prompt_names();
             system_prompts()
                 .iter()
                 .map(|system_prompt| {
                     let name = system_prompt.name.clone();
                     let selected = selected_prompt_name.clone() == Some(name.clone());
+                    let mentioned = active_prompt_names.contains(&name);
                     view! {
                         <button
                             data-size="compact"
                             data-role="outline"
                             class="chat-controls-system-prompt"
                             data-selected=selected.to_string()
+                            data-mentioned=mentioned.to_string()
                             on:click={
                                 let selected_prompt_name = selected_prompt_name.clone();
                                 move |_| {
@@ -39,5 +47,15 @@ pub fn SystemPromptBar(
                 })
                 .collect_view()
         }}
+        {move || {
+            (active_prompt_names().len() > 1)
+                .then(|| {
+                    view! {
+                        <system-prompt-bar-hint style="font-size:0.75em; opacity:0.7; padding:0 4px;">
+                            {format!("{} prompts active", active_prompt_names().len())}
+                        </system-prompt-bar-hint>
+                    }
+                })
+        }}
     }
 }
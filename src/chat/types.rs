@@ -0,0 +1,86 @@
+use crate::llm;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct SystemPrompt {
+    pub name: String,
+    pub prompt: String,
+    /// Default values for `{{variable}}` placeholders in `prompt`, applied
+    /// before any `key=value` overrides parsed off an `@name` mention (see
+    /// `chat::extract_mentions`). A `Vec` rather than a `HashMap` so prompt
+    /// JSON round-trips in a stable, diffable order.
+    #[serde(default)]
+    pub variables: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct MessageCost {
+    pub prompt: f64,
+    pub completion: f64,
+}
+
+impl MessageCost {
+    /// Prices `usage`'s token counts against `model_info`'s per-million
+    /// rates. Missing rates (OpenRouter didn't report pricing for this
+    /// model) are treated as free rather than unknown.
+    pub fn from_usage(usage: llm::Usage, model_info: &llm::DisplayModelInfo) -> Self {
+        MessageCost {
+            prompt: model_info.prompt_cost_usd_pm.unwrap_or(0.0) * usage.prompt_tokens as f64
+                / 1_000_000.0,
+            completion: model_info.completion_cost_usd_pm.unwrap_or(0.0)
+                * usage.completion_tokens as f64
+                / 1_000_000.0,
+        }
+    }
+}
+
+/// Generates a fresh id for a message. Also used as the `serde(default)`
+/// for `Message::id` so messages persisted before branching existed (no
+/// `id` field at all) deserialize with a usable, unique one.
+pub fn new_message_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Message {
+    /// Stable for this message's lifetime. Lets `parent_id` and
+    /// `ChatSession::active_path` reference it.
+    #[serde(default = "new_message_id")]
+    pub id: String,
+    /// The message this one follows, or `None` for the first message of a
+    /// branch. Regenerating creates a new message with the same
+    /// `parent_id` as the reply it's replacing (a sibling, not a child),
+    /// so the old reply stays reachable as a branch instead of being
+    /// deleted — see `persistence::switch_branch_path`.
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    pub role: String,
+    pub content: String,
+    pub prompt_name: Option<String>,
+    pub system_prompt_content: Option<String>,
+    pub model_name: Option<String>,
+    pub cost: Option<MessageCost>,
+    /// Set while an assistant message was cut short by cancellation, so the
+    /// UI can offer a "Continue" action instead of treating it as finished.
+    #[serde(default)]
+    pub interrupted: bool,
+    /// Live char-level diff against the text this message is replacing,
+    /// populated while a regenerate/edit re-submission is streaming in and
+    /// cleared once the stream settles on its final text. Never persisted.
+    #[serde(skip)]
+    pub diff_hunks: Option<Vec<crate::diff::Hunk>>,
+    /// Set while a dropped stream is being retried with backoff, e.g.
+    /// `"retrying (2/4)…"`. Cleared once the stream reconnects. Never
+    /// persisted.
+    #[serde(skip)]
+    pub retry_status: Option<String>,
+}
+
+impl Message {
+    pub fn to_llm(&self) -> llm::Message {
+        llm::Message {
+            role: self.role.clone(),
+            content: self.content.clone(),
+        }
+    }
+}
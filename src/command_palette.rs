@@ -0,0 +1,204 @@
+//! A global, keyboard-first command palette (opened with Ctrl/Cmd+K) for
+//! running actions without the mouse: navigating sessions, switching the
+//! active model or system prompt, opening settings, and cancelling an
+//! in-flight request.
+
+use leptos::html;
+use leptos::prelude::*;
+use leptos_router::hooks::use_navigate;
+use std::rc::Rc;
+
+use crate::state::GlobalState;
+
+#[derive(Clone)]
+struct PaletteCommand {
+    label: String,
+    run: Rc<dyn Fn()>,
+}
+
+/// True if every character of `query` appears in `text`, in order
+/// (case-insensitive), i.e. `query` is a subsequence of `text`.
+fn subsequence_match(query: &str, text: &str) -> bool {
+    if query.is_empty() {
+        return true;
+    }
+    let mut chars = text.to_lowercase().chars();
+    query
+        .to_lowercase()
+        .chars()
+        .all(|qc| chars.by_ref().any(|tc| tc == qc))
+}
+
+#[component]
+pub fn CommandPalette(
+    #[prop(into)] open: RwSignal<bool>,
+    #[prop(into)] on_prev: Callback<()>,
+    #[prop(into)] on_next: Callback<()>,
+) -> impl IntoView {
+    let state = use_context::<GlobalState>().expect("GlobalState not found");
+    let navigate = use_navigate();
+    let query = RwSignal::new(String::new());
+    let highlighted_index = RwSignal::new(0usize);
+    let input_ref: NodeRef<html::Input> = NodeRef::new();
+
+    Effect::new(move |_| {
+        if open.get() {
+            query.set(String::new());
+            highlighted_index.set(0);
+            if let Some(input) = input_ref.get() {
+                let _ = input.focus();
+            }
+        }
+    });
+
+    let commands = Memo::new(move |_| {
+        let mut commands: Vec<PaletteCommand> = Vec::new();
+
+        commands.push(PaletteCommand {
+            label: "New chat".to_string(),
+            run: {
+                let navigate = navigate.clone();
+                Rc::new(move || navigate("/chat/new", Default::default()))
+            },
+        });
+
+        commands.push(PaletteCommand {
+            label: "Previous session".to_string(),
+            run: Rc::new(move || on_prev.run(())),
+        });
+
+        commands.push(PaletteCommand {
+            label: "Next session".to_string(),
+            run: Rc::new(move || on_next.run(())),
+        });
+
+        commands.push(PaletteCommand {
+            label: "Open settings".to_string(),
+            run: {
+                let navigate = navigate.clone();
+                Rc::new(move || navigate("/settings", Default::default()))
+            },
+        });
+
+        if state.cancel_request.get().is_some() {
+            commands.push(PaletteCommand {
+                label: "Cancel in-flight request".to_string(),
+                run: Rc::new(move || {
+                    if let Some(cancel) = state.cancel_request.get_untracked() {
+                        cancel.run(());
+                    }
+                }),
+            });
+        }
+
+        for model in state.cached_models.get() {
+            let set_model_name = state.set_model_name;
+            let model_id = model.id.clone();
+            commands.push(PaletteCommand {
+                label: format!("Switch model: {}", model.name),
+                run: Rc::new(move || set_model_name.set(model_id.clone())),
+            });
+        }
+
+        for prompt in state.system_prompts.get() {
+            let set_selected_prompt_name = state.set_selected_prompt_name;
+            let prompt_name = prompt.name.clone();
+            commands.push(PaletteCommand {
+                label: format!("Switch prompt: @{}", prompt.name),
+                run: Rc::new(move || set_selected_prompt_name.set(Some(prompt_name.clone()))),
+            });
+        }
+
+        commands
+    });
+
+    let filtered_commands = Memo::new(move |_| {
+        let query = query.get();
+        commands
+            .get()
+            .into_iter()
+            .filter(|command| subsequence_match(&query, &command.label))
+            .collect::<Vec<_>>()
+    });
+
+    let run_command = move |command: PaletteCommand| {
+        (command.run)();
+        open.set(false);
+    };
+
+    view! {
+        <Show when=move || open.get()>
+            <div
+                class="command-palette-backdrop"
+                style="position:fixed; inset:0; background:rgba(0,0,0,0.4); z-index:100; display:flex; align-items:flex-start; justify-content:center;"
+                on:click=move |_| open.set(false)
+            >
+                <div
+                    class="command-palette"
+                    style="margin-top:10vh; width:min(560px, 90vw); background:var(--background-color); border:1px solid var(--border-color); border-radius:8px; overflow:hidden;"
+                    on:click=move |ev| ev.stop_propagation()
+                >
+                    <input
+                        type="text"
+                        node_ref=input_ref
+                        placeholder="Type a command..."
+                        style="width:100%; padding:8px; border:none; border-bottom:1px solid var(--border-color);"
+                        prop:value=query
+                        on:input:target=move |ev| {
+                            query.set(ev.target().value());
+                            highlighted_index.set(0);
+                        }
+                        on:keydown=move |ev| {
+                            match ev.key().as_str() {
+                                "Escape" => open.set(false),
+                                "ArrowDown" => {
+                                    ev.prevent_default();
+                                    let len = filtered_commands.get_untracked().len();
+                                    if len > 0 {
+                                        highlighted_index.update(|i| *i = (*i + 1) % len);
+                                    }
+                                }
+                                "ArrowUp" => {
+                                    ev.prevent_default();
+                                    let len = filtered_commands.get_untracked().len();
+                                    if len > 0 {
+                                        highlighted_index.update(|i| *i = (*i + len - 1) % len);
+                                    }
+                                }
+                                "Enter" => {
+                                    let commands = filtered_commands.get_untracked();
+                                    if let Some(command) = commands.get(highlighted_index.get_untracked()) {
+                                        run_command(command.clone());
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                    />
+                    <ul style="max-height:50vh; overflow-y:auto; margin:0; padding:0; list-style:none;">
+                        {move || {
+                            filtered_commands
+                                .get()
+                                .into_iter()
+                                .enumerate()
+                                .map(|(index, command)| {
+                                    let is_highlighted = Memo::new(move |_| highlighted_index.get() == index);
+                                    let command_for_click = command.clone();
+                                    view! {
+                                        <li
+                                            style="padding:8px; cursor:pointer; border-bottom:1px solid var(--border-color);"
+                                            class:command-palette-item-highlighted=is_highlighted
+                                            on:click=move |_| run_command(command_for_click.clone())
+                                        >
+                                            {command.label.clone()}
+                                        </li>
+                                    }
+                                })
+                                .collect_view()
+                        }}
+                    </ul>
+                </div>
+            </div>
+        </Show>
+    }
+}
@@ -0,0 +1,167 @@
+//! Approximate per-session token and cost accounting.
+//!
+//! This module runs no tokenizer, so its token counts are only an estimate: a blend of
+//! word count and `chars / 4`, which tracks real BPE tokenizers closely
+//! enough for a rough running total. Pricing comes from the
+//! [`crate::llm::DisplayModelInfo`] already fetched for the model picker, so
+//! switching models re-prices the visible history.
+//!
+//! This module's [`estimate_tokens`] is its own heuristic, independent of
+//! [`crate::tokenizer::estimate_tokens`]'s pretokenize-then-BPE-merge one
+//! (over a small hand-curated merge table) used for the composer's live
+//! budget indicator — the two estimates
+//! differ slightly by design and neither is a real cl100k_base BPE encoder.
+
+use leptos::prelude::*;
+
+use crate::chat::types::Message;
+use crate::llm::DisplayModelInfo;
+
+/// Estimates the token count of `text` as `max(1, 0.75 * words + 0.25 * chars / 4)`.
+pub fn estimate_tokens(text: &str) -> f64 {
+    if text.is_empty() {
+        return 0.0;
+    }
+    let chars = text.chars().count() as f64;
+    let words = text.split_whitespace().count() as f64;
+    (0.75 * words + 0.25 * (chars / 4.0)).max(1.0)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TokenTally {
+    pub prompt_tokens: f64,
+    pub completion_tokens: f64,
+}
+
+impl TokenTally {
+    pub fn total_tokens(&self) -> f64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    pub fn prompt_cost_usd(&self, model: &DisplayModelInfo) -> f64 {
+        model.prompt_cost_usd_pm.unwrap_or(0.0) * self.prompt_tokens / 1_000_000.0
+    }
+
+    pub fn cost_usd(&self, model: &DisplayModelInfo) -> f64 {
+        let completion_cost =
+            model.completion_cost_usd_pm.unwrap_or(0.0) * self.completion_tokens / 1_000_000.0;
+        self.prompt_cost_usd(model) + completion_cost
+    }
+}
+
+/// Fraction of `model`'s context window the given tally would occupy, or
+/// `None` if the model didn't report a `context_length`.
+pub fn context_window_fraction(tally: &TokenTally, model: &DisplayModelInfo) -> Option<f64> {
+    model
+        .context_length
+        .map(|context_length| tally.total_tokens() / context_length as f64)
+}
+
+/// Sums the already-settled USD cost of a session (from `Message::cost`,
+/// populated once each response's `Usage` arrives), for comparing against a
+/// budget ceiling alongside the projected cost of a not-yet-sent request.
+pub fn actual_session_cost_usd(messages: &[Message]) -> f64 {
+    messages
+        .iter()
+        .filter_map(|m| m.cost)
+        .map(|c| c.prompt + c.completion)
+        .sum()
+}
+
+/// Sums estimated tokens across `messages`, bucketing assistant output
+/// separately from everything sent as input (user/system).
+pub fn tally_messages(messages: &[Message]) -> TokenTally {
+    let mut tally = TokenTally::default();
+    for message in messages {
+        let tokens = estimate_tokens(&message.content);
+        if message.role == "assistant" {
+            tally.completion_tokens += tokens;
+        } else {
+            tally.prompt_tokens += tokens;
+        }
+    }
+    tally
+}
+
+/// Tokens for just the most recent turn (the trailing run of messages back to
+/// the last user message), used for the per-turn delta.
+fn last_turn_tally(messages: &[Message]) -> TokenTally {
+    let mut turn = Vec::new();
+    for message in messages.iter().rev() {
+        turn.push(message.clone());
+        if message.role == "user" {
+            break;
+        }
+    }
+    tally_messages(&turn)
+}
+
+/// Shows the estimated token count and USD cost for the current session,
+/// alongside the delta from the most recent turn. Re-prices reactively when
+/// `model_name` changes.
+#[component]
+pub fn CostTracker(
+    #[prop(into)] messages: Signal<Vec<Message>>,
+    #[prop(into)] model_name: Signal<String>,
+    #[prop(into)] cached_models: Signal<Vec<DisplayModelInfo>>,
+    #[prop(into)] pending_input: Signal<String>,
+    #[prop(into)] budget_ceiling_usd: Signal<Option<f64>>,
+    #[prop(into)] set_budget_ceiling_usd: WriteSignal<Option<f64>>,
+) -> impl IntoView {
+    let session_tally = Memo::new(move |_| tally_messages(&messages.get()));
+    let turn_tally = Memo::new(move |_| last_turn_tally(&messages.get()));
+    let current_model = Memo::new(move |_| {
+        let name = model_name.get();
+        cached_models.get().into_iter().find(|m| m.id == name)
+    });
+    // What the next submission would cost/occupy: the session so far plus
+    // whatever is currently typed but not yet sent.
+    let projected_tally = Memo::new(move |_| {
+        let mut tally = session_tally.get();
+        tally.prompt_tokens += estimate_tokens(&pending_input.get());
+        tally
+    });
+
+    view! {
+        <chat-cost-tracker style="display:flex; flex-wrap:wrap; justify-content:flex-end; align-items:center; gap:12px; padding:2px 8px; font-size:0.75em; opacity:0.7;">
+            {move || {
+                let session = session_tally.get();
+                match current_model.get() {
+                    Some(model) => {
+                        let turn = turn_tally.get();
+                        let projected = projected_tally.get();
+                        let context_line = context_window_fraction(&projected, &model)
+                            .map(|fraction| format!(" · {:.0}% of context window", fraction * 100.0))
+                            .unwrap_or_default();
+                        format!(
+                            "~{:.0} tokens · ${:.4} session (+${:.4} last turn, ~${:.4} next send){}",
+                            session.total_tokens(),
+                            session.cost_usd(&model),
+                            turn.cost_usd(&model),
+                            projected.prompt_cost_usd(&model),
+                            context_line,
+                        )
+                    }
+                    None => format!("~{:.0} tokens · no pricing for this model", session.total_tokens()),
+                }
+            }}
+            <span style="display:flex; align-items:center; gap:4px;">
+                "budget ceiling $"
+                <input
+                    type="number"
+                    step="0.01"
+                    min="0"
+                    style="width:5em;"
+                    prop:value=move || {
+                        budget_ceiling_usd.get().map(|v| v.to_string()).unwrap_or_default()
+                    }
+                    on:input:target=move |ev| {
+                        let value = ev.target().value();
+                        set_budget_ceiling_usd.set(value.parse::<f64>().ok());
+                    }
+                    placeholder="none"
+                />
+            </span>
+        </chat-cost-tracker>
+    }
+}
@@ -0,0 +1,210 @@
+//! Incremental character-level diff between a fixed "old" text and a "new"
+//! text that arrives in chunks, used to render regenerating/editing messages
+//! as a live diff instead of a flicker between two unrelated texts.
+
+/// A single piece of the diff between the old and new text, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Hunk {
+    /// `n` characters common to both old and new text.
+    Keep(usize),
+    /// Characters present only in the new text.
+    Insert(String),
+    /// `n` characters present only in the old text.
+    Delete(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Keep,
+    Insert(char),
+    Delete,
+}
+
+/// How many trailing new-text columns are left un-finalized on each `push`,
+/// since the optimal alignment near the streaming frontier can still change
+/// as more text arrives.
+const STABLE_LAG: usize = 6;
+
+const MATCH_BASE: i64 = 2;
+const MATCH_RUN_CAP: i64 = 8;
+const MISMATCH_PENALTY: i64 = -1;
+const GAP_PENALTY: i64 = -1;
+
+/// Online edit-distance diff engine. Feed it new text with [`push`], and read
+/// back the hunks that are now stable; call [`finish`] once the new text is
+/// complete to get the remaining, final hunks.
+pub struct StreamingDiff {
+    old: Vec<char>,
+    new: Vec<char>,
+    /// `score[i][j]`: best alignment score of `old[..i]` against `new[..j]`.
+    score: Vec<Vec<i64>>,
+    /// `run[i][j]`: length of the consecutive matching-diagonal run ending
+    /// at `(i, j)`, used only to bias the scoring toward longer `Keep` runs.
+    run: Vec<Vec<u32>>,
+    /// Ops already handed out to the caller via `push`/`finish`, so later
+    /// calls only need to return the newly-stable suffix.
+    committed: Vec<Op>,
+}
+
+impl StreamingDiff {
+    pub fn new(old_text: &str) -> Self {
+        let old: Vec<char> = old_text.chars().collect();
+        let m = old.len();
+        let mut score = vec![vec![0i64]; m + 1];
+        let run = vec![vec![0u32]; m + 1];
+        for (i, row) in score.iter_mut().enumerate() {
+            row[0] = -(i as i64);
+        }
+        Self {
+            old,
+            new: Vec::new(),
+            score,
+            run,
+            committed: Vec::new(),
+        }
+    }
+
+    /// Appends `chunk` to the received new text and returns the hunks that
+    /// have become stable since the last call.
+    pub fn push(&mut self, chunk: &str) -> Vec<Hunk> {
+        for ch in chunk.chars() {
+            self.grow_column(ch);
+        }
+        let cutoff = self.new.len().saturating_sub(STABLE_LAG);
+        self.emit_up_to(cutoff)
+    }
+
+    /// Finalizes the diff, returning any remaining hunks.
+    pub fn finish(&mut self) -> Vec<Hunk> {
+        self.emit_up_to(self.new.len())
+    }
+
+    fn grow_column(&mut self, ch: char) {
+        self.new.push(ch);
+        let j = self.new.len();
+        let m = self.old.len();
+        for i in 0..=m {
+            self.score[i].push(0);
+            self.run[i].push(0);
+        }
+        for i in 0..=m {
+            let up = if i > 0 {
+                self.score[i - 1][j] + GAP_PENALTY
+            } else {
+                i64::MIN / 2
+            };
+            let left = self.score[i][j - 1] + GAP_PENALTY;
+            let (diag, diag_run) = if i > 0 {
+                let prev_run = self.run[i - 1][j - 1];
+                if self.old[i - 1] == ch {
+                    let new_run = prev_run + 1;
+                    let bonus = (new_run as i64).min(MATCH_RUN_CAP);
+                    (self.score[i - 1][j - 1] + MATCH_BASE + bonus, new_run)
+                } else {
+                    (self.score[i - 1][j - 1] + MISMATCH_PENALTY, 0)
+                }
+            } else {
+                (i64::MIN / 2, 0)
+            };
+
+            let best = up.max(left).max(diag);
+            self.score[i][j] = best;
+            self.run[i][j] = if best == diag { diag_run } else { 0 };
+        }
+    }
+
+    /// Backtracks from `(old.len(), new.len())` to `(0, 0)`, returning ops in
+    /// forward order paired with how many `new` characters have been
+    /// consumed once that op is applied.
+    fn backtrack(&self) -> Vec<(Op, usize)> {
+        let mut i = self.old.len();
+        let mut j = self.new.len();
+        let mut ops = Vec::new();
+        while i > 0 || j > 0 {
+            if i > 0 && j > 0 {
+                let diag = self.score[i - 1][j - 1]
+                    + if self.old[i - 1] == self.new[j - 1] {
+                        MATCH_BASE + (self.run[i - 1][j - 1] as i64 + 1).min(MATCH_RUN_CAP)
+                    } else {
+                        MISMATCH_PENALTY
+                    };
+                if self.score[i][j] == diag {
+                    if self.old[i - 1] == self.new[j - 1] {
+                        ops.push((Op::Keep, j));
+                    } else {
+                        // Substitution: the old char is dropped and the new
+                        // char is inserted, both consuming column `j`.
+                        ops.push((Op::Insert(self.new[j - 1]), j));
+                        ops.push((Op::Delete, j));
+                    }
+                    i -= 1;
+                    j -= 1;
+                    continue;
+                }
+            }
+            if j > 0 && self.score[i][j] == self.score[i][j - 1] + GAP_PENALTY {
+                ops.push((Op::Insert(self.new[j - 1]), j));
+                j -= 1;
+                continue;
+            }
+            // Remaining case: delete old[i - 1]; doesn't consume `new`.
+            ops.push((Op::Delete, j));
+            i -= 1;
+        }
+        ops.reverse();
+        ops
+    }
+
+    /// Recomputes the full alignment, keeps the prefix ending at or before
+    /// `cutoff` new-text columns, and returns only the ops beyond what was
+    /// already committed (the alignment near the frontier can still be
+    /// revised by later chunks, so we diff against the previous commit
+    /// rather than assuming a pure append).
+    fn emit_up_to(&mut self, cutoff: usize) -> Vec<Hunk> {
+        let full = self.backtrack();
+        let stable: Vec<Op> = full
+            .into_iter()
+            .take_while(|(_, col)| *col <= cutoff)
+            .map(|(op, _)| op)
+            .collect();
+        let common = self
+            .committed
+            .iter()
+            .zip(stable.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let fresh = stable[common..].to_vec();
+        self.committed = stable;
+        encode(&fresh)
+    }
+}
+
+fn encode(ops: &[Op]) -> Vec<Hunk> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    for op in ops {
+        match op {
+            Op::Keep => {
+                if let Some(Hunk::Keep(n)) = hunks.last_mut() {
+                    *n += 1;
+                } else {
+                    hunks.push(Hunk::Keep(1));
+                }
+            }
+            Op::Delete => {
+                if let Some(Hunk::Delete(n)) = hunks.last_mut() {
+                    *n += 1;
+                } else {
+                    hunks.push(Hunk::Delete(1));
+                }
+            }
+            Op::Insert(ch) => {
+                if let Some(Hunk::Insert(s)) = hunks.last_mut() {
+                    s.push(*ch);
+                } else {
+                    hunks.push(Hunk::Insert(ch.to_string()));
+                }
+            }
+        }
+    }
+    hunks
+}
@@ -0,0 +1,57 @@
+//! Small DOM helpers that don't belong to any one component.
+
+use leptos::prelude::window;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+/// Whether the document is currently hidden (backgrounded tab, minimized
+/// window, etc.), per the Page Visibility API.
+pub fn is_document_hidden() -> bool {
+    window()
+        .document()
+        .map(|doc| doc.hidden())
+        .unwrap_or(false)
+}
+
+/// Sets the browser tab title.
+pub fn set_title(title: &str) {
+    if let Some(doc) = window().document() {
+        doc.set_title(title);
+    }
+}
+
+/// Reads a browser `File`'s contents as UTF-8 text.
+pub async fn read_file_as_text(file: &web_sys::File) -> Result<String, JsValue> {
+    let value = JsFuture::from(file.text()).await?;
+    value
+        .as_string()
+        .ok_or_else(|| JsValue::from_str("File content was not a string"))
+}
+
+/// Triggers a browser download of `contents` as `filename`. There's no
+/// direct "save to disk" API, so this builds a throwaway Blob URL and
+/// clicks an invisible anchor pointed at it.
+pub fn download_text_file(filename: &str, contents: &str) {
+    let Some(document) = window().document() else {
+        return;
+    };
+
+    let parts = js_sys::Array::new();
+    parts.push(&JsValue::from_str(contents));
+    let Ok(blob) = web_sys::Blob::new_with_str_sequence(&parts) else {
+        return;
+    };
+    let Ok(url) = web_sys::Url::create_object_url_with_blob(&blob) else {
+        return;
+    };
+
+    if let Ok(element) = document.create_element("a") {
+        if let Ok(anchor) = element.dyn_into::<web_sys::HtmlAnchorElement>() {
+            anchor.set_href(&url);
+            anchor.set_download(filename);
+            anchor.click();
+        }
+    }
+
+    let _ = web_sys::Url::revoke_object_url(&url);
+}
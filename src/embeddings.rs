@@ -0,0 +1,116 @@
+//! Semantic retrieval across past sessions.
+//!
+//! Messages are embedded lazily after they're stored (see the effect in
+//! `ChatInterface` that watches `messages` for a freshly-settled assistant
+//! reply) and persisted via [`crate::persistence::EmbeddingRecord`].
+//! `ChatInterface::submit` embeds the user's new input and ranks it against
+//! that corpus with [`top_k_similar`] to decide what prior context, if any,
+//! to inject.
+//!
+//! Embedding requests go through [`crate::provider::Provider::embed`], same
+//! as chat completions and model listing, so a user configured for OpenAI
+//! or Ollama doesn't get silently misrouted to OpenRouter's host/auth.
+
+use crate::persistence::EmbeddingRecord;
+use crate::provider::ProviderKind;
+use anyhow::Result;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+pub const EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
+/// Requests an embedding vector for each of `texts`, in the same order, from
+/// `provider_kind`.
+pub async fn embed_texts(
+    provider_kind: ProviderKind,
+    texts: Vec<String>,
+    api_key: String,
+) -> Result<Vec<Vec<f32>>> {
+    crate::llm::embed_texts(provider_kind, texts, EMBEDDING_MODEL, api_key).await
+}
+
+/// Rescales `v` to unit length in place. A zero vector is left as-is (its
+/// cosine similarity against anything is then just 0).
+fn normalize(v: &mut [f32]) {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for x in v.iter_mut() {
+            *x /= norm;
+        }
+    }
+}
+
+/// Cosine similarity of two already-unit-length vectors is just their dot
+/// product.
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// A prior message retrieved as relevant context, along with the score it
+/// was ranked by.
+#[derive(Debug, Clone)]
+pub struct RetrievedContext {
+    pub message_id: String,
+    pub session_id: String,
+    pub content: String,
+    pub score: f32,
+}
+
+/// Order by score only, so `BinaryHeap<Reverse<ScoredCandidate>>` is a
+/// min-heap on score — popping evicts the weakest candidate, keeping the
+/// `k` strongest.
+struct ScoredCandidate {
+    score: f32,
+    record: EmbeddingRecord,
+}
+
+impl PartialEq for ScoredCandidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.score == other.score
+    }
+}
+impl Eq for ScoredCandidate {}
+impl PartialOrd for ScoredCandidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.score.partial_cmp(&other.score)
+    }
+}
+impl Ord for ScoredCandidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.partial_cmp(other).unwrap_or(Ordering::Equal)
+    }
+}
+
+/// The `k` records in `corpus` most similar to `query`, highest score
+/// first. Keeps a bounded min-heap of size `k` while scanning rather than
+/// sorting the whole corpus.
+pub fn top_k_similar(query: &[f32], corpus: &[EmbeddingRecord], k: usize) -> Vec<RetrievedContext> {
+    if k == 0 {
+        return Vec::new();
+    }
+
+    let mut query = query.to_vec();
+    normalize(&mut query);
+
+    let mut heap: BinaryHeap<std::cmp::Reverse<ScoredCandidate>> = BinaryHeap::with_capacity(k + 1);
+    for record in corpus {
+        let mut vector = record.vector.clone();
+        normalize(&mut vector);
+        let score = cosine_similarity(&query, &vector);
+        heap.push(std::cmp::Reverse(ScoredCandidate { score, record: record.clone() }));
+        if heap.len() > k {
+            heap.pop();
+        }
+    }
+
+    let mut top: Vec<ScoredCandidate> = heap.into_iter().map(|std::cmp::Reverse(c)| c).collect();
+    top.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    top.into_iter()
+        .map(|c| RetrievedContext {
+            message_id: c.record.message_id,
+            session_id: c.record.session_id,
+            content: c.record.content,
+            score: c.score,
+        })
+        .collect()
+}
@@ -1,5 +1,6 @@
 use crate::chat::types::SystemPrompt;
 use crate::chat::SystemPromptBar;
+use crate::prompt_picker::PromptPicker;
 use leptos::prelude::*;
 use leptos_router::hooks::use_navigate;
 use leptos::ev;
@@ -40,12 +41,36 @@ pub fn Header(
         }
     };
 
+    let prompt_picker_open = use_context::<crate::state::GlobalState>()
+        .expect("GlobalState not found")
+        .prompt_picker_open;
+
+    // Drives the bar's "N prompts active" hint from whatever's typed in the
+    // composer right now, not just the single manually-selected prompt.
+    let active_prompt_names = Memo::new(move |_| {
+        crate::chat::mentioned_prompt_names(&global_state.input.get(), &system_prompts.get())
+    });
+
     view! {
         <header>
             <SystemPromptBar
                 system_prompts=system_prompts
                 selected_prompt_name=selected_prompt_name
                 set_selected_prompt_name=set_selected_prompt_name
+                active_prompt_names=Signal::derive(move || active_prompt_names.get())
+            />
+            <button
+                data-size="compact"
+                data-role="outline"
+                title="Fuzzy-search system prompts"
+                on:click=move |_| prompt_picker_open.set(true)
+            >
+                "@"
+            </button>
+            <PromptPicker
+                open=prompt_picker_open
+                system_prompts=system_prompts
+                set_selected_prompt_name=set_selected_prompt_name
             />
             <button
                 data-size="compact"
@@ -0,0 +1,70 @@
+//! A centralized keyboard-shortcut layer: every binding is a declarative
+//! key-combo -> action entry in a list registered once at app init (see
+//! `install`), dispatched from a single `keydown` listener, instead of
+//! scattering `on:keydown` handlers across components. Keeps bindings in one
+//! place so they could later be made user-configurable.
+
+use leptos::prelude::*;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlElement, KeyboardEvent};
+
+/// One shortcut: the key as reported by `KeyboardEvent::key()` (matched
+/// case-insensitively) plus the modifiers that must be held, and the action
+/// to run when it fires.
+pub struct KeyBinding {
+    pub key: &'static str,
+    pub alt: bool,
+    pub ctrl: bool,
+    pub meta: bool,
+    pub shift: bool,
+    pub action: Rc<dyn Fn()>,
+}
+
+impl KeyBinding {
+    /// A binding with no modifiers held.
+    pub fn plain(key: &'static str, action: impl Fn() + 'static) -> Self {
+        KeyBinding { key, alt: false, ctrl: false, meta: false, shift: false, action: Rc::new(action) }
+    }
+
+    /// A binding that fires while Alt is held (and no other modifier).
+    pub fn alt(key: &'static str, action: impl Fn() + 'static) -> Self {
+        KeyBinding { key, alt: true, ctrl: false, meta: false, shift: false, action: Rc::new(action) }
+    }
+
+    fn matches(&self, event: &KeyboardEvent) -> bool {
+        event.key().eq_ignore_ascii_case(self.key)
+            && event.alt_key() == self.alt
+            && event.ctrl_key() == self.ctrl
+            && event.meta_key() == self.meta
+            && event.shift_key() == self.shift
+    }
+}
+
+/// True if `event` was dispatched while focus was inside a text-entry
+/// element (`<input>`/`<textarea>`, e.g. in `Settings` or
+/// `SystemPromptItem`), where shortcuts should fall through to normal typing
+/// instead of firing.
+fn focus_is_in_text_field(event: &KeyboardEvent) -> bool {
+    let Some(target) = event.target() else {
+        return false;
+    };
+    let Ok(element) = target.dyn_into::<HtmlElement>() else {
+        return false;
+    };
+    matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA")
+}
+
+/// Registers `bindings` on a single global `keydown` listener, suppressed
+/// while focus is inside a text field. Call once at app init.
+pub fn install(bindings: Vec<KeyBinding>) {
+    let _ = window_event_listener(leptos::ev::keydown, move |ev: KeyboardEvent| {
+        if focus_is_in_text_field(&ev) {
+            return;
+        }
+        if let Some(binding) = bindings.iter().find(|binding| binding.matches(&ev)) {
+            ev.prevent_default();
+            (binding.action)();
+        }
+    });
+}
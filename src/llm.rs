@@ -1,3 +1,4 @@
+use crate::provider::{self, ProviderCreds, ProviderKind};
 use futures::{Stream, StreamExt};
 use openrouter_api::{
     types::chat::{ChatCompletionRequest, Message as OpenRouterMessage},
@@ -30,25 +31,114 @@ impl Message {
     }
 }
 
+/// A function the model may call, in the OpenAI/OpenRouter "function tool"
+/// shape: `parameters` is a JSON Schema object describing the arguments.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Tool {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+impl Tool {
+    /// OpenRouter/OpenAI wrap function tools as `{"type": "function", "function": {...}}`.
+    fn to_openrouter_tool(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": self.name,
+                "description": self.description,
+                "parameters": self.parameters,
+            }
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Model {
     pub model: String,        // e.g., "openai/gpt-4o"
     pub seed: Option<i64>,    // OpenRouter might not directly support seed for all models in the same way
     pub temperature: Option<f64>,
+    /// Which backend `model` is addressed to. `#[serde(default)]` so
+    /// `Model`s persisted before this field existed load as `OpenRouter`,
+    /// the only backend that used to exist.
+    #[serde(default)]
+    pub provider: ProviderKind,
+    /// Function tools the model may call. Empty by default, so existing
+    /// `Model`s (persisted or freshly constructed) behave exactly as before.
+    #[serde(default)]
+    pub tools: Vec<Tool>,
+    /// Caps the completion length for reasoning models (o1-style), which use
+    /// this instead of the legacy `max_tokens`. `None` leaves it up to the
+    /// backend's default.
+    #[serde(default)]
+    pub max_completion_tokens: Option<u32>,
+    /// Overrides the client's request timeout. Reasoning models can take
+    /// minutes to respond, far longer than the 60s default that's fine for
+    /// regular chat completions. `None` keeps that default.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// The request timeout used when a `Model` doesn't override it.
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// Token usage for a single completion, reported once OpenRouter sends the
+/// final chunk of a streamed response.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct Usage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+/// One item of a streamed completion: a chunk of assistant text, a fully
+/// assembled tool call (OpenRouter streams these incrementally, keyed by
+/// index, until `finish_reason == "tool_calls"`; see `openrouter_chat_stream`
+/// for the accumulation), or the usage totals that arrive with the final
+/// chunk.
+#[derive(Debug, Clone)]
+pub enum StreamedMessage {
+    Content(String),
+    ToolCall { id: String, name: String, arguments: String },
+    Usage(Usage),
+}
+
+/// Accumulates one in-progress tool call's streamed fragments (id, function
+/// name, and partial JSON arguments arrive across several chunks).
+#[derive(Debug, Clone, Default)]
+struct ToolCallAccumulator {
+    id: String,
+    name: String,
+    arguments: String,
 }
 
+/// Streams a chat completion through whichever backend `model_config.provider`
+/// selects. Dispatches through [`provider::provider_for`]; see `provider.rs`
+/// for the OpenAI/Ollama adapters.
 pub async fn request_message_content_streamed(
     messages: Vec<Message>,
     model_config: Model,
     api_key: String,
-) -> anyhow::Result<impl Stream<Item = anyhow::Result<String>>> {
+) -> anyhow::Result<impl Stream<Item = anyhow::Result<StreamedMessage>>> {
+    let provider_kind = model_config.provider;
+    provider::provider_for(provider_kind)
+        .chat_stream(messages, model_config, ProviderCreds { api_key })
+        .await
+}
+
+/// The OpenRouter-specific implementation backing `OpenRouterProvider`.
+pub(crate) async fn openrouter_chat_stream(
+    messages: Vec<Message>,
+    model_config: Model,
+    api_key: String,
+) -> anyhow::Result<impl Stream<Item = anyhow::Result<StreamedMessage>>> {
     if api_key.is_empty() {
         return Err(anyhow::anyhow!("OpenRouter API key is missing."));
     }
 
     let client = OpenRouterClient::new()
         .with_base_url("https://openrouter.ai/api/v1/")?
-        .with_timeout_secs(60) // Configure timeout before setting API key
+        .with_timeout_secs(model_config.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS)) // Configure timeout before setting API key
         .with_api_key(api_key.clone())?; // API key is the final step to get a Ready client
 
     let openrouter_messages: Vec<OpenRouterMessage> = messages
@@ -56,13 +146,25 @@ pub async fn request_message_content_streamed(
         .map(|m| m.to_openrouter_message())
         .collect();
 
+    let tools = if model_config.tools.is_empty() {
+        None
+    } else {
+        Some(
+            model_config
+                .tools
+                .iter()
+                .map(Tool::to_openrouter_tool)
+                .collect(),
+        )
+    };
+
     let request = ChatCompletionRequest {
         model: model_config.model,
         messages: openrouter_messages,
         stream: Some(true),
         // Explicitly set other optional fields to None as ChatCompletionRequest doesn't implement Default
         response_format: None,
-        tools: None,
+        tools,
         provider: None,
         models: None,
         transforms: None,
@@ -77,18 +179,53 @@ pub async fn request_message_content_streamed(
     let mut stream = chat_api.chat_completion_stream(request);
 
     let output_stream = async_stream::stream! {
+        // Tool-call fragments arrive incrementally (partial name, partial
+        // JSON arguments) keyed by the call's position in the response;
+        // accumulate until the chunk carrying `finish_reason == "tool_calls"`
+        // tells us they're complete.
+        let mut tool_calls: std::collections::BTreeMap<usize, ToolCallAccumulator> =
+            std::collections::BTreeMap::new();
+
         while let Some(chunk_result) = stream.next().await {
             match chunk_result {
                 Ok(chunk) => {
-                    // Process the chunk
                     // A chunk can have multiple choices, but for typical streaming, we expect one.
                     if let Some(choice) = chunk.choices.first() {
                         if let Some(content) = &choice.delta.content {
-                            yield Ok(content.clone());
+                            yield Ok(StreamedMessage::Content(content.clone()));
+                        }
+                        if let Some(deltas) = &choice.delta.tool_calls {
+                            for delta in deltas {
+                                let entry = tool_calls.entry(delta.index).or_default();
+                                if let Some(id) = &delta.id {
+                                    entry.id = id.clone();
+                                }
+                                if let Some(function) = &delta.function {
+                                    if let Some(name) = &function.name {
+                                        entry.name.push_str(name);
+                                    }
+                                    if let Some(arguments) = &function.arguments {
+                                        entry.arguments.push_str(arguments);
+                                    }
+                                }
+                            }
+                        }
+                        if choice.finish_reason.as_deref() == Some("tool_calls") {
+                            for (_, call) in std::mem::take(&mut tool_calls) {
+                                yield Ok(StreamedMessage::ToolCall {
+                                    id: call.id,
+                                    name: call.name,
+                                    arguments: call.arguments,
+                                });
+                            }
                         }
                     }
-                    // You might also want to handle other parts of the chunk, e.g., finish_reason or usage.
-                    // For now, we only care about content.
+                    if let Some(usage) = chunk.usage {
+                        yield Ok(StreamedMessage::Usage(Usage {
+                            prompt_tokens: usage.prompt_tokens as u32,
+                            completion_tokens: usage.completion_tokens as u32,
+                        }));
+                    }
                 }
                 Err(e) => {
                     // Log the error or handle it as appropriate
@@ -103,12 +240,186 @@ pub async fn request_message_content_streamed(
     Ok(output_stream)
 }
 
+/// Issues a blocking (non-streaming) chat completion. Reasoning models
+/// (o1-preview/o1-mini style) reject `stream: true`, so
+/// `request_message_content_streamed`/`openrouter_chat_stream` can't be used
+/// for them; this goes straight to OpenRouter's REST endpoint with
+/// `stream: false` instead of going through `openrouter_api`'s
+/// `ChatCompletionRequest`, which (per the comment above) doesn't expose
+/// `max_completion_tokens`.
+pub async fn request_message_content(
+    messages: Vec<Message>,
+    model_config: Model,
+    api_key: String,
+) -> anyhow::Result<String> {
+    blocking_chat_completion(messages, model_config, api_key, None, RequestOptions::default()).await
+}
+
+/// Retry/timeout knobs for the blocking REST path
+/// (`request_message_content`/`request_structured`). The streaming path
+/// already gets reconnect-with-backoff from `chat::request::RetryPolicy` at
+/// its call site; this path had no equivalent, so it gets its own here.
+///
+/// There's deliberately no proxy option: this crate only targets wasm32,
+/// where `reqwest` is backed by the browser's `fetch`, which doesn't expose
+/// manual proxy configuration (the browser/OS handles that transparently).
+#[derive(Debug, Clone, Copy)]
+pub struct RequestOptions {
+    /// Caps how long the initial connection may take, separate from
+    /// `Model::timeout_secs` (the overall request timeout).
+    pub connect_timeout_secs: Option<u64>,
+    /// How many times to retry a transient (429/5xx) failure, with
+    /// exponential backoff, before giving up.
+    pub max_retries: u32,
+}
+
+impl Default for RequestOptions {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: None,
+            max_retries: 3,
+        }
+    }
+}
+
+/// Shared by `request_message_content` and `request_structured`: a blocking
+/// (`stream: false`) POST straight to OpenRouter's REST endpoint, optionally
+/// constraining the response to `response_format`. Retries transient 429/5xx
+/// responses with exponential backoff before giving up.
+async fn blocking_chat_completion(
+    messages: Vec<Message>,
+    model_config: Model,
+    api_key: String,
+    response_format: Option<serde_json::Value>,
+    options: RequestOptions,
+) -> anyhow::Result<String> {
+    if api_key.is_empty() {
+        return Err(anyhow::anyhow!("OpenRouter API key is missing."));
+    }
+
+    let mut body = serde_json::json!({
+        "model": model_config.model,
+        "messages": messages.iter().map(|m| serde_json::json!({"role": m.role, "content": m.content})).collect::<Vec<_>>(),
+        "stream": false,
+        "max_completion_tokens": model_config.max_completion_tokens,
+    });
+    if let Some(response_format) = response_format {
+        body["response_format"] = response_format;
+    }
+
+    let mut client_builder = reqwest::Client::builder().timeout(std::time::Duration::from_secs(
+        model_config.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS),
+    ));
+    if let Some(connect_timeout_secs) = options.connect_timeout_secs {
+        client_builder =
+            client_builder.connect_timeout(std::time::Duration::from_secs(connect_timeout_secs));
+    }
+    let client = client_builder.build()?;
+
+    let mut attempt = 0;
+    let response = loop {
+        let result = client
+            .post("https://openrouter.ai/api/v1/chat/completions")
+            .bearer_auth(&api_key)
+            .json(&body)
+            .send()
+            .await;
+
+        match result {
+            Ok(response) if response.status().is_success() => break response,
+            Ok(response) => {
+                let status = response.status();
+                let transient = status.as_u16() == 429 || status.is_server_error();
+                if transient && attempt < options.max_retries {
+                    attempt += 1;
+                    gloo_timers::future::TimeoutFuture::new(backoff_delay_ms(attempt)).await;
+                    continue;
+                }
+                let body_text = response.text().await.unwrap_or_default();
+                return Err(anyhow::anyhow!(
+                    "OpenRouter request failed ({status}): {body_text}"
+                ));
+            }
+            Err(e) => {
+                if attempt < options.max_retries {
+                    attempt += 1;
+                    gloo_timers::future::TimeoutFuture::new(backoff_delay_ms(attempt)).await;
+                    continue;
+                }
+                return Err(anyhow::anyhow!("Request to OpenRouter failed: {e}"));
+            }
+        }
+    };
+
+    #[derive(Deserialize)]
+    struct ResponseChoiceMessage {
+        content: String,
+    }
+    #[derive(Deserialize)]
+    struct ResponseChoice {
+        message: ResponseChoiceMessage,
+    }
+    #[derive(Deserialize)]
+    struct ChatCompletionResponse {
+        choices: Vec<ResponseChoice>,
+    }
+
+    let parsed: ChatCompletionResponse = response.json().await?;
+    parsed
+        .choices
+        .into_iter()
+        .next()
+        .map(|choice| choice.message.content)
+        .ok_or_else(|| anyhow::anyhow!("No choices found in OpenRouter response"))
+}
+
+/// Exponential backoff (attempt is 1-indexed), capped the same way
+/// `chat::request::backoff_delay_ms` caps its own schedule.
+fn backoff_delay_ms(attempt: u32) -> u32 {
+    500u32.saturating_mul(1u32 << attempt.min(6))
+}
+
+/// Requests a completion constrained to `T`'s JSON Schema (via `schemars`)
+/// and deserializes the single choice's content into `T`. Useful for
+/// extraction/classification tasks where free-form text isn't usable.
+pub async fn request_structured<T: serde::de::DeserializeOwned + schemars::JsonSchema>(
+    messages: Vec<Message>,
+    model_config: Model,
+    api_key: String,
+) -> anyhow::Result<T> {
+    let schema = schemars::schema_for!(T);
+    let response_format = serde_json::json!({
+        "type": "json_schema",
+        "json_schema": {
+            "name": "response",
+            "schema": schema,
+            "strict": true,
+        },
+    });
+
+    let content =
+        blocking_chat_completion(
+            messages,
+            model_config,
+            api_key,
+            Some(response_format),
+            RequestOptions::default(),
+        )
+        .await?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("Model response was not valid JSON for the requested schema: {e}. Raw response: {content}"))
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 pub struct DisplayModelInfo {
     pub id: String,
     pub name: String,
     pub prompt_cost_usd_pm: Option<f64>, // Cost per million prompt tokens
     pub completion_cost_usd_pm: Option<f64>, // Cost per million completion tokens
+    /// Model's total context window in tokens, used to drive the "context
+    /// window used" indicator. `None` if OpenRouter didn't report one.
+    pub context_length: Option<u32>,
 }
 
 // Helper function to parse price string and convert to per million tokens
@@ -116,7 +427,34 @@ fn parse_price_to_per_million(price_str: &str) -> Option<f64> {
     price_str.parse::<f64>().ok().map(|p| p * 1_000_000.0)
 }
 
-pub async fn list_available_models(api_key: String) -> anyhow::Result<Vec<DisplayModelInfo>> {
+/// Lists the models available from `provider_kind`. Dispatches through
+/// [`provider::provider_for`].
+pub async fn list_available_models(
+    provider_kind: ProviderKind,
+    api_key: String,
+) -> anyhow::Result<Vec<DisplayModelInfo>> {
+    provider::provider_for(provider_kind)
+        .list_models(ProviderCreds { api_key })
+        .await
+}
+
+/// Requests an embedding vector for each of `texts` from `provider_kind`.
+/// Dispatches through [`provider::provider_for`]; providers without an
+/// embeddings API (see [`provider::Provider::embed`]'s default) return an
+/// error rather than silently hitting the wrong host.
+pub async fn embed_texts(
+    provider_kind: ProviderKind,
+    texts: Vec<String>,
+    model: &str,
+    api_key: String,
+) -> anyhow::Result<Vec<Vec<f32>>> {
+    provider::provider_for(provider_kind)
+        .embed(texts, model, ProviderCreds { api_key })
+        .await
+}
+
+/// The OpenRouter-specific implementation backing `OpenRouterProvider`.
+pub(crate) async fn openrouter_list_models(api_key: String) -> anyhow::Result<Vec<DisplayModelInfo>> {
     if api_key.is_empty() {
         return Err(anyhow::anyhow!("OpenRouter API key is missing."));
     }
@@ -137,12 +475,66 @@ pub async fn list_available_models(api_key: String) -> anyhow::Result<Vec<Displa
             name: m.name, // m.name is String, not Option<String>
             prompt_cost_usd_pm: parse_price_to_per_million(&m.pricing.prompt),
             completion_cost_usd_pm: parse_price_to_per_million(&m.pricing.completion),
+            context_length: m.context_length.map(|cl| cl as u32),
         })
         .collect();
 
     Ok(model_infos)
 }
 
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbeddingDatum {
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingDatum>,
+}
+
+/// The OpenRouter-specific implementation backing `OpenRouterProvider`.
+/// `openrouter_api` doesn't expose an embeddings endpoint, so this is a raw
+/// `reqwest` POST, the same workaround `blocking_chat_completion` uses for
+/// request shapes the crate doesn't cover.
+pub(crate) async fn openrouter_embed(
+    texts: Vec<String>,
+    model: &str,
+    api_key: String,
+) -> anyhow::Result<Vec<Vec<f32>>> {
+    if texts.is_empty() {
+        return Ok(Vec::new());
+    }
+    if api_key.is_empty() {
+        return Err(anyhow::anyhow!("OpenRouter API key is missing."));
+    }
+
+    let response = reqwest::Client::new()
+        .post("https://openrouter.ai/api/v1/embeddings")
+        .bearer_auth(api_key)
+        .json(&EmbeddingRequest { model, input: &texts })
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("Embedding request failed: {e}"))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!("Embedding request failed with status {status}: {body}"));
+    }
+
+    let parsed: EmbeddingResponse = response
+        .json()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to parse embedding response: {e}"))?;
+    Ok(parsed.data.into_iter().map(|d| d.embedding).collect())
+}
+
 // --- Old OpenAI specific code commented out for reference or later porting ---
 /*
 use reqwest::Client;
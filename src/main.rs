@@ -1,21 +1,40 @@
 mod chat;
 mod chat_page;
 mod combobox;
+mod command_palette;
 mod copy_button;
+mod cost_tracker;
+mod diff;
 mod dom_utils;
+mod embeddings;
 mod header;
+mod keymap;
 mod llm;
+mod notifications;
 mod persistence;
+mod prompt_expansion;
+mod prompt_library;
+mod prompt_picker;
+mod provider;
+mod session_naming;
+mod session_store;
+mod session_switcher;
 mod state;
+mod tokenizer;
 pub mod markdown;
 mod settings;
+mod usage;
 pub mod utils;
 
 use crate::chat::types::{Message, SystemPrompt};
 use crate::header::Header;
 use crate::chat_page::ChatPage;
 use crate::llm::DisplayModelInfo;
-use crate::persistence::ChatSession;
+use crate::persistence::{ChatSession, PromptRecord};
+use crate::provider::ProviderKind;
+use crate::command_palette::CommandPalette;
+use crate::session_store::SessionStore;
+use crate::session_switcher::SessionSwitcher;
 use crate::state::GlobalState;
 use crate::settings::Settings;
 use codee::string::{FromToStringCodec, JsonSerdeCodec};
@@ -30,8 +49,18 @@ use leptos_use::use_debounce_fn;
 use web_sys::js_sys::Date;
 
 
+/// The backend used for chat session persistence. IndexedDB is the only
+/// implementor wired in today, but call sites only depend on
+/// [`SessionStore`], so swapping to [`session_store::LocalStorageSessionStore`]
+/// (e.g. for browsers where IndexedDB is blocked) or
+/// [`session_store::MemorySessionStore`] (tests, SSR) is a one-line change here.
+fn session_store() -> Box<dyn SessionStore> {
+    Box::new(session_store::IdbSessionStore)
+}
+
 fn main() {
     console_error_panic_hook::set_once();
+    notifications::install_focus_listener();
     mount_to_body(App);
 }
 
@@ -49,12 +78,27 @@ fn MainContent() -> impl IntoView {
     // --- LIFTED STATE ---
     let (api_key, set_api_key, _) =
         use_local_storage::<String, FromToStringCodec>("OPENROUTER_API_KEY");
-    let (system_prompts, set_system_prompts, _) =
-        use_local_storage::<Vec<SystemPrompt>, JsonSerdeCodec>("system_prompts");
+    // Prompt library, backed by IndexedDB (see `persistence::PromptRecord`).
+    let prompt_library = RwSignal::new(Vec::<PromptRecord>::new());
+    let system_prompts = Signal::derive(move || {
+        prompt_library
+            .get()
+            .into_iter()
+            .map(|p| SystemPrompt {
+                name: p.title,
+                prompt: p.body,
+                variables: p.variables,
+            })
+            .collect::<Vec<_>>()
+    });
     let (model_name_storage, set_model_name_storage, _) =
         use_local_storage::<String, FromToStringCodec>("MODEL_NAME");
+    let (provider_kind, set_provider_kind, _) =
+        use_local_storage::<ProviderKind, JsonSerdeCodec>("provider_kind");
     let (cached_models, set_cached_models, _) =
         use_local_storage::<Vec<DisplayModelInfo>, JsonSerdeCodec>("cached_models");
+    let (budget_ceiling_usd, set_budget_ceiling_usd, _) =
+        use_local_storage::<Option<f64>, JsonSerdeCodec>("budget_ceiling_usd");
     let (input, set_input, _) = use_local_storage::<String, FromToStringCodec>("input");
     let (selected_prompt_name, set_selected_prompt_name, _) =
         use_local_storage::<Option<String>, JsonSerdeCodec>("selected_prompt_name");
@@ -66,6 +110,8 @@ fn MainContent() -> impl IntoView {
     // --- Current Session State ---
     // Current session state
     let messages = RwSignal::new(Vec::<Message>::new());
+    // Full branch tree backing `messages` (the active path); see `GlobalState::all_messages`.
+    let all_messages = RwSignal::new(Vec::<Message>::new());
     let error = RwSignal::new(None::<String>);
     let current_session_id = RwSignal::new(None::<String>);
     // --- Child-to-Parent Communication ---
@@ -73,6 +119,19 @@ fn MainContent() -> impl IntoView {
     let navigation_request = RwSignal::new(None::<String>);
     let initial_chat_prompt = RwSignal::new(None::<String>);
 
+    // --- Session Switcher State ---
+    let all_sessions = RwSignal::new(Vec::<ChatSession>::new());
+    let session_switcher_open = RwSignal::new(false);
+
+    // --- Command Palette State ---
+    let command_palette_open = RwSignal::new(false);
+    let cancel_request = RwSignal::new(None::<Callback<()>>);
+
+    // --- Prompt Picker State ---
+    let prompt_picker_open = RwSignal::new(false);
+
+    let usage = RwSignal::new(Vec::<persistence::UsageRollup>::new());
+
     let debounced_save_session = use_debounce_fn(
         move || {
             let session_id_to_save = current_session_id.get();
@@ -83,26 +142,66 @@ fn MainContent() -> impl IntoView {
                     return;
                 }
                 spawn_local(async move {
-                    let existing_session = persistence::load_session(&session_id_to_save).await.ok().flatten();
+                    let existing_session = session_store().load(&session_id_to_save).await.ok().flatten();
                     let is_new_session = existing_session.is_none();
                     let session_prompt_name = msgs_to_save
                         .first()
                         .filter(|m| m.role == "system")
                         .and_then(|m| m.prompt_name.clone());
 
+                    let name = match existing_session.as_ref().and_then(|s| s.name.clone()) {
+                        Some(name) => Some(name),
+                        None => {
+                            let existing_names: Vec<String> = all_sessions
+                                .get_untracked()
+                                .iter()
+                                .filter_map(|s| s.name.clone())
+                                .collect();
+                            Some(session_naming::generate_session_name(
+                                Date::now() as u64,
+                                &existing_names,
+                            ))
+                        }
+                    };
+
+                    let active_path: Vec<String> = msgs_to_save.iter().map(|m| m.id.clone()).collect();
                     let session_to_save_db = ChatSession {
                         session_id: session_id_to_save.clone(),
                         messages: msgs_to_save,
-                        name: None,
+                        active_path,
+                        name,
                         created_at_ms: existing_session.map_or_else(Date::now, |s| s.created_at_ms),
                         updated_at_ms: Date::now(),
                         prompt_name: session_prompt_name,
                     };
 
-                    if persistence::save_session(&session_to_save_db).await.is_ok() && is_new_session {
-                        sorted_session_ids.update(|ids| {
-                            if !ids.iter().any(|id| id == &session_id_to_save) {
-                                ids.insert(0, session_id_to_save);
+                    if session_store().save(&session_to_save_db).await.is_ok() {
+                        // `save` merges the active path we just sent into the
+                        // full branch tree already on disk, so reload to pick
+                        // up that merged tree for `all_messages`.
+                        let merged_session = session_store()
+                            .load(&session_id_to_save)
+                            .await
+                            .ok()
+                            .flatten()
+                            .unwrap_or_else(|| session_to_save_db.clone());
+                        all_messages.set(merged_session.messages.clone());
+
+                        if is_new_session {
+                            sorted_session_ids.update(|ids| {
+                                if !ids.iter().any(|id| id == &session_id_to_save) {
+                                    ids.insert(0, session_id_to_save);
+                                }
+                            });
+                        }
+                        all_sessions.update(|sessions| {
+                            if let Some(existing) = sessions
+                                .iter_mut()
+                                .find(|s| s.session_id == merged_session.session_id)
+                            {
+                                *existing = merged_session;
+                            } else {
+                                sessions.insert(0, merged_session);
                             }
                         });
                     }
@@ -112,18 +211,179 @@ fn MainContent() -> impl IntoView {
         2000.0,
     );
 
+    // Switches the active conversation to the branch containing `message_id`
+    // (e.g. an alternate regenerated reply), resolved against `all_messages`,
+    // then saves so the new active path persists.
+    let switch_branch = Callback::new({
+        let debounced_save_session = debounced_save_session.clone();
+        move |message_id: String| {
+            let tree = all_messages.get_untracked();
+            let new_path_ids = persistence::switch_branch_path(&tree, &message_id);
+            let by_id: std::collections::HashMap<&str, &Message> =
+                tree.iter().map(|m| (m.id.as_str(), m)).collect();
+            let new_messages: Vec<Message> = new_path_ids
+                .iter()
+                .filter_map(|id| by_id.get(id.as_str()).map(|m| (*m).clone()))
+                .collect();
+            messages.set(new_messages);
+            debounced_save_session();
+        }
+    });
+
+    let rename_session = Callback::new(move |(session_id, name): (String, String)| {
+        spawn_local(async move {
+            match persistence::rename_session(&session_id, name.clone()).await {
+                Ok(()) => {
+                    all_sessions.update(|sessions| {
+                        if let Some(session) = sessions.iter_mut().find(|s| s.session_id == session_id) {
+                            session.name = Some(name);
+                        }
+                    });
+                }
+                Err(e) => error.set(Some(format!("Failed to rename session: {e}"))),
+            }
+        });
+    });
+
+    let delete_session = Callback::new(move |session_id: String| {
+        spawn_local(async move {
+            match persistence::soft_delete_session(&session_id, Date::now()).await {
+                Ok(()) => {
+                    all_sessions.update(|sessions| {
+                        if let Some(session) = sessions.iter_mut().find(|s| s.session_id == session_id) {
+                            session.deleted_at_ms = Some(Date::now());
+                        }
+                    });
+                    sorted_session_ids.update(|ids| ids.retain(|id| id != &session_id));
+                    if current_session_id.get_untracked().as_deref() == Some(session_id.as_str()) {
+                        current_session_id.set(None);
+                        messages.set(vec![]);
+                        all_messages.set(vec![]);
+                        navigation_request.set(Some("/chat/new".to_string()));
+                    }
+                }
+                Err(e) => error.set(Some(format!("Failed to delete session: {e}"))),
+            }
+        });
+    });
+
+    let restore_session = Callback::new(move |session_id: String| {
+        spawn_local(async move {
+            match persistence::restore_session(&session_id).await {
+                Ok(()) => {
+                    all_sessions.update(|sessions| {
+                        if let Some(session) = sessions.iter_mut().find(|s| s.session_id == session_id) {
+                            session.deleted_at_ms = None;
+                        }
+                    });
+                    sorted_session_ids.update(|ids| {
+                        if !ids.iter().any(|id| id == &session_id) {
+                            ids.insert(0, session_id.clone());
+                        }
+                    });
+                }
+                Err(e) => error.set(Some(format!("Failed to restore session: {e}"))),
+            }
+        });
+    });
+
+    let delete_all_sessions = Callback::new(move |()| {
+        spawn_local(async move {
+            let now = Date::now();
+            match persistence::soft_delete_all_sessions(now).await {
+                Ok(()) => {
+                    all_sessions.update(|sessions| {
+                        for session in sessions.iter_mut() {
+                            session.deleted_at_ms = Some(now);
+                        }
+                    });
+                    sorted_session_ids.set(vec![]);
+                    current_session_id.set(None);
+                    messages.set(vec![]);
+                    all_messages.set(vec![]);
+                    navigation_request.set(Some("/chat/new".to_string()));
+                }
+                Err(e) => error.set(Some(format!("Failed to delete all sessions: {e}"))),
+            }
+        });
+    });
+
+    let save_prompt = Callback::new(move |prompt: PromptRecord| {
+        prompt_library.update(|prompts| {
+            if let Some(existing) = prompts.iter_mut().find(|p| p.id == prompt.id) {
+                *existing = prompt.clone();
+            } else {
+                prompts.push(prompt.clone());
+            }
+        });
+        spawn_local(async move {
+            if let Err(e) = persistence::save_prompt(&prompt).await {
+                error.set(Some(format!("Failed to save prompt: {e}")));
+            }
+        });
+    });
+
+    let delete_prompt = Callback::new(move |prompt_id: String| {
+        prompt_library.update(|prompts| prompts.retain(|p| p.id != prompt_id));
+        spawn_local(async move {
+            if let Err(e) = persistence::delete_prompt(&prompt_id).await {
+                error.set(Some(format!("Failed to delete prompt: {e}")));
+            }
+        });
+    });
+
+    // Folds a settled `Usage`/`MessageCost` into today's rollup for
+    // `model_name`, optimistically in memory and then persisted.
+    let record_usage = Callback::new(
+        move |(model_name, msg_usage, cost): (String, llm::Usage, chat::types::MessageCost)| {
+            let date = Date::new_0().to_iso_string().as_string().unwrap_or_default()[0..10].to_string();
+            usage.update(|rollups| {
+                let rollup_key = persistence::UsageRollup::rollup_key(&date, &model_name);
+                if let Some(existing) = rollups.iter_mut().find(|r| r.rollup_key == rollup_key) {
+                    existing.prompt_tokens += msg_usage.prompt_tokens as u64;
+                    existing.completion_tokens += msg_usage.completion_tokens as u64;
+                    existing.prompt_cost_usd += cost.prompt;
+                    existing.completion_cost_usd += cost.completion;
+                } else {
+                    rollups.push(persistence::UsageRollup {
+                        rollup_key,
+                        date: date.clone(),
+                        model_name: model_name.clone(),
+                        prompt_tokens: msg_usage.prompt_tokens as u64,
+                        completion_tokens: msg_usage.completion_tokens as u64,
+                        prompt_cost_usd: cost.prompt,
+                        completion_cost_usd: cost.completion,
+                    });
+                }
+            });
+            spawn_local(async move {
+                if let Err(e) = persistence::record_usage_rollup(&date, &model_name, &msg_usage, &cost).await {
+                    leptos::logging::log!("[WARN] [MainContent] Failed to persist usage rollup: {e}");
+                }
+            });
+        },
+    );
+
     let global_state = GlobalState {
         api_key,
         set_api_key,
         system_prompts,
-        set_system_prompts,
+        prompt_library,
+        save_prompt,
+        delete_prompt,
         model_name: model_name_storage,
         set_model_name: set_model_name_storage,
+        provider_kind,
+        set_provider_kind,
         input,
         set_input,
         cached_models,
         set_cached_models,
+        budget_ceiling_usd,
+        set_budget_ceiling_usd,
         messages,
+        all_messages,
+        switch_branch,
         selected_prompt_name,
         set_selected_prompt_name,
         error,
@@ -131,6 +391,17 @@ fn MainContent() -> impl IntoView {
         session_load_request: set_session_load_request,
         navigation_request,
         initial_chat_prompt,
+        all_sessions,
+        session_switcher_open,
+        rename_session,
+        delete_session,
+        restore_session,
+        delete_all_sessions,
+        command_palette_open,
+        cancel_request,
+        prompt_picker_open,
+        usage,
+        record_usage,
         save_session: Callback::new(move |_| { debounced_save_session(); }),
     };
     provide_context(global_state.clone());
@@ -138,9 +409,19 @@ fn MainContent() -> impl IntoView {
     // --- Actions (triggered by effects) ---
     let load_session_list = StoredValue::new(move || {
         spawn_local(async move {
-            match persistence::get_all_session_keys_sorted_by_update().await {
-                Ok(keys) => {
-                    sorted_session_ids.set(keys);
+            if let Err(e) = persistence::purge_expired_trash(Date::now()).await {
+                leptos::logging::log!("[WARN] [MainContent] Failed to purge expired trash: {e}");
+            }
+            match persistence::load_all_sessions().await {
+                Ok(sessions) => {
+                    sorted_session_ids.set(
+                        sessions
+                            .iter()
+                            .filter(|s| s.deleted_at_ms.is_none())
+                            .map(|s| s.session_id.clone())
+                            .collect(),
+                    );
+                    all_sessions.set(sessions);
                 }
                 Err(e) => error.set(Some(format!("Failed to load session list: {e}"))),
             }
@@ -155,6 +436,30 @@ fn MainContent() -> impl IntoView {
         }
     });
 
+    // Initial load of the prompt library
+    Effect::new(move |prev: Option<()>| {
+        if prev.is_none() {
+            spawn_local(async move {
+                match persistence::load_all_prompts().await {
+                    Ok(prompts) => prompt_library.set(prompts),
+                    Err(e) => error.set(Some(format!("Failed to load prompt library: {e}"))),
+                }
+            });
+        }
+    });
+
+    // Initial load of usage rollups
+    Effect::new(move |prev: Option<()>| {
+        if prev.is_none() {
+            spawn_local(async move {
+                match persistence::load_all_usage_rollups().await {
+                    Ok(rollups) => usage.set(rollups),
+                    Err(e) => error.set(Some(format!("Failed to load usage rollups: {e}"))),
+                }
+            });
+        }
+    });
+
     // When the app loads, check if the stored prompt name is still valid.
     Effect::new(move |_| {
         let all_prompts = system_prompts.get();
@@ -183,6 +488,7 @@ fn MainContent() -> impl IntoView {
             if id_to_load == "new" {
                 global_state.current_session_id.set(None);
                 messages.set(vec![]);
+                all_messages.set(vec![]);
                 // When starting a new chat, we preserve the selected system prompt
                 error.set(None);
             }
@@ -194,10 +500,11 @@ fn MainContent() -> impl IntoView {
             // 2.1.3. Load existing sessions from IndexedDB
             else {
                 spawn_local(async move {
-                    match persistence::load_session(&id_to_load).await {
+                    match session_store().load(&id_to_load).await {
                         Ok(Some(session)) => {
                             global_state.current_session_id.set(Some(session.session_id.clone()));
-                            messages.set(session.messages);
+                            messages.set(persistence::resolve_active_path(&session));
+                            all_messages.set(session.messages.clone());
                             global_state.set_selected_prompt_name.set(session.prompt_name);
                             error.set(None);
                         }
@@ -314,8 +621,63 @@ fn MainContent() -> impl IntoView {
         }
     };
 
+    let on_prev_cmd = Callback::new({
+        let on_prev = on_prev.clone();
+        move |_: ()| on_prev(web_sys::MouseEvent::new("click").expect("failed to build synthetic MouseEvent"))
+    });
+    let on_next_cmd = Callback::new({
+        let on_next = on_next.clone();
+        move |_: ()| on_next(web_sys::MouseEvent::new("click").expect("failed to build synthetic MouseEvent"))
+    });
+
+    // Centralized keyboard-shortcut layer (see `keymap`): navigation,
+    // starting a new chat, and opening the prompt picker, all without the
+    // mouse and suppressed while a text field has focus.
+    keymap::install(vec![
+        keymap::KeyBinding {
+            key: "k",
+            alt: false,
+            ctrl: true,
+            meta: false,
+            shift: false,
+            action: std::rc::Rc::new(move || command_palette_open.update(|open| *open = !*open)),
+        },
+        keymap::KeyBinding {
+            key: "k",
+            alt: false,
+            ctrl: false,
+            meta: true,
+            shift: false,
+            action: std::rc::Rc::new(move || command_palette_open.update(|open| *open = !*open)),
+        },
+        keymap::KeyBinding::alt("ArrowLeft", move || {
+            if can_go_prev.get_untracked() {
+                on_prev_cmd.run(());
+            }
+        }),
+        keymap::KeyBinding::alt("ArrowRight", move || {
+            if can_go_next.get_untracked() {
+                on_next_cmd.run(());
+            }
+        }),
+        keymap::KeyBinding::alt("n", {
+            let navigate = use_navigate();
+            move || navigate("/chat/new", Default::default())
+        }),
+        keymap::KeyBinding::alt("p", move || prompt_picker_open.set(true)),
+    ]);
+
     view! {
         <Header />
+        <button
+            data-size="compact"
+            title="Switch session (search by name or content)"
+            on:click=move |_| session_switcher_open.set(true)
+        >
+            "Switch Session"
+        </button>
+        <SessionSwitcher open=session_switcher_open sessions=Signal::derive(move || all_sessions.get()) />
+        <CommandPalette open=command_palette_open on_prev=on_prev_cmd on_next=on_next_cmd />
         <Routes fallback=|| view! { <h1>"Not Found"</h1> }>
             <Route
                 path=path!("/chat/:id")
@@ -338,8 +700,8 @@ fn MainContent() -> impl IntoView {
                         <Settings
                             api_key=state.api_key
                             set_api_key=state.set_api_key
-                            system_prompts=state.system_prompts
-                            set_system_prompts=state.set_system_prompts
+                            provider_kind=state.provider_kind
+                            set_provider_kind=state.set_provider_kind
                         />
                     }
                 }
@@ -0,0 +1,298 @@
+//! Minimal markdown renderer for assistant chat messages: block structure
+//! (headings, lists, blockquotes, inline code, fenced code) plus a small
+//! per-language keyword highlighter for fenced code blocks.
+//!
+//! Parsing is streaming-safe: an unterminated fence (no closing ``` yet,
+//! because the response is still generating) is rendered as an open code
+//! block instead of leaking into a paragraph and breaking layout.
+
+use leptos::prelude::*;
+
+use crate::copy_button::CopyButton;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Heading { level: u8, text: String },
+    BlockQuote(String),
+    ListItem { text: String },
+    Code { lang: Option<String>, code: String },
+    Paragraph(String),
+}
+
+fn strip_list_marker(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    if let Some(rest) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+        return Some(rest);
+    }
+    // Ordered list markers like "1. " or "12. ".
+    let digits_end = trimmed.find(|c: char| !c.is_ascii_digit())?;
+    if digits_end == 0 {
+        return None;
+    }
+    trimmed[digits_end..].strip_prefix(". ")
+}
+
+fn parse_blocks(markdown_text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut lines = markdown_text.lines().peekable();
+    let mut paragraph_buf: Vec<String> = Vec::new();
+
+    macro_rules! flush_paragraph {
+        () => {
+            if !paragraph_buf.is_empty() {
+                blocks.push(Block::Paragraph(paragraph_buf.join("\n")));
+                paragraph_buf.clear();
+            }
+        };
+    }
+
+    while let Some(line) = lines.next() {
+        if let Some(lang) = line.trim_start().strip_prefix("```") {
+            flush_paragraph!();
+            let lang = (!lang.trim().is_empty()).then(|| lang.trim().to_string());
+            let mut code_lines = Vec::new();
+            for code_line in lines.by_ref() {
+                if code_line.trim_start().starts_with("```") {
+                    break;
+                }
+                code_lines.push(code_line.to_string());
+            }
+            // Whether or not a closing fence was found, render what we have:
+            // if the stream hasn't sent the closer yet, this is just the
+            // code block as it stands so far.
+            blocks.push(Block::Code { lang, code: code_lines.join("\n") });
+        } else if let Some(text) = line.trim_start().strip_prefix("### ") {
+            flush_paragraph!();
+            blocks.push(Block::Heading { level: 3, text: text.to_string() });
+        } else if let Some(text) = line.trim_start().strip_prefix("## ") {
+            flush_paragraph!();
+            blocks.push(Block::Heading { level: 2, text: text.to_string() });
+        } else if let Some(text) = line.trim_start().strip_prefix("# ") {
+            flush_paragraph!();
+            blocks.push(Block::Heading { level: 1, text: text.to_string() });
+        } else if let Some(text) = line.trim_start().strip_prefix("> ") {
+            flush_paragraph!();
+            blocks.push(Block::BlockQuote(text.to_string()));
+        } else if let Some(text) = strip_list_marker(line) {
+            flush_paragraph!();
+            blocks.push(Block::ListItem { text: text.to_string() });
+        } else if line.trim().is_empty() {
+            flush_paragraph!();
+        } else {
+            paragraph_buf.push(line.to_string());
+        }
+    }
+    flush_paragraph!();
+    blocks
+}
+
+/// Renders inline `` `code` `` spans within otherwise plain text.
+fn render_inline(text: &str) -> impl IntoView {
+    text.split('`')
+        .enumerate()
+        .map(|(i, segment)| {
+            if i % 2 == 1 {
+                view! { <code class="md-inline-code">{segment.to_string()}</code> }.into_any()
+            } else {
+                segment.to_string().into_any()
+            }
+        })
+        .collect_view()
+}
+
+struct LanguageRules {
+    keywords: &'static [&'static str],
+}
+
+/// The pluggable part of the highlighter: one keyword set per recognized
+/// `language-xxx` token. Adding a language is just another match arm here —
+/// `render_code` below stays unchanged.
+fn language_rules(lang: &str) -> Option<LanguageRules> {
+    match lang.to_lowercase().as_str() {
+        "rust" | "rs" => Some(LanguageRules {
+            keywords: &[
+                "fn", "let", "mut", "pub", "struct", "enum", "impl", "match", "if", "else",
+                "for", "while", "loop", "return", "use", "mod", "trait", "self", "Self", "async",
+                "await", "move", "ref", "const", "static", "true", "false", "as", "dyn",
+            ],
+        }),
+        "javascript" | "js" | "typescript" | "ts" | "jsx" | "tsx" => Some(LanguageRules {
+            keywords: &[
+                "function", "const", "let", "var", "if", "else", "for", "while", "return",
+                "class", "extends", "import", "export", "from", "async", "await", "new", "this",
+                "true", "false", "null", "undefined", "typeof",
+            ],
+        }),
+        "python" | "py" => Some(LanguageRules {
+            keywords: &[
+                "def", "class", "if", "elif", "else", "for", "while", "return", "import", "from",
+                "as", "with", "try", "except", "finally", "lambda", "None", "True", "False",
+                "async", "await", "yield", "self",
+            ],
+        }),
+        "go" => Some(LanguageRules {
+            keywords: &[
+                "func", "package", "import", "var", "const", "type", "struct", "interface", "if",
+                "else", "for", "range", "return", "go", "chan", "select", "defer", "switch",
+                "case", "default", "true", "false", "nil",
+            ],
+        }),
+        "bash" | "sh" | "shell" => Some(LanguageRules {
+            keywords: &[
+                "if", "then", "else", "elif", "fi", "for", "do", "done", "while", "function",
+                "case", "esac", "echo", "export", "local", "return", "true", "false",
+            ],
+        }),
+        "json" => Some(LanguageRules { keywords: &["true", "false", "null"] }),
+        _ => None,
+    }
+}
+
+/// Highlights `code` token-by-token for a known language; falls back to an
+/// unhighlighted `<pre>` when the language is missing or unrecognized.
+fn render_code(lang: &Option<String>, code: &str) -> impl IntoView {
+    let rules = lang.as_deref().and_then(language_rules);
+    match rules {
+        Some(rules) => {
+            let tokens = tokenize(code);
+            let spans = tokens
+                .into_iter()
+                .map(|token| {
+                    let class = if rules.keywords.contains(&token.as_str()) {
+                        "md-code-keyword"
+                    } else if token.starts_with('"') || token.starts_with('\'') {
+                        "md-code-string"
+                    } else if token.starts_with("//") || token.starts_with('#') {
+                        "md-code-comment"
+                    } else {
+                        "md-code-plain"
+                    };
+                    view! { <span class=class>{token}</span> }
+                })
+                .collect_view();
+            view! { <pre class="md-code-block"><code>{spans}</code></pre> }.into_any()
+        }
+        None => view! { <pre class="md-code-block"><code>{code.to_string()}</code></pre> }.into_any(),
+    }
+}
+
+/// Splits source into a flat token stream of words, whitespace, strings and
+/// punctuation, preserving enough structure for the keyword pass above while
+/// staying a single lightweight function (no lexer crate).
+fn tokenize(code: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = code.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '"' || c == '\'' {
+            let quote = c;
+            let mut s = String::from(c);
+            for next in chars.by_ref() {
+                s.push(next);
+                if next == quote {
+                    break;
+                }
+            }
+            tokens.push(s);
+        } else if c == '/' && chars.peek() == Some(&'/') {
+            let mut s = String::from(c);
+            for next in chars.by_ref() {
+                s.push(next);
+            }
+            tokens.push(s);
+        } else if c == '#' {
+            let mut s = String::from(c);
+            for next in chars.by_ref() {
+                s.push(next);
+            }
+            tokens.push(s);
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut s = String::from(c);
+            while let Some(next) = chars.peek() {
+                if next.is_alphanumeric() || *next == '_' {
+                    s.push(*next);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            tokens.push(s);
+        } else {
+            tokens.push(c.to_string());
+        }
+    }
+    tokens
+}
+
+/// Renders `markdown_text` as structured, syntax-highlighted blocks. Every
+/// block except a list item gets its own copy-to-clipboard button.
+#[component]
+pub fn Markdown(#[prop(into)] markdown_text: String) -> impl IntoView {
+    let blocks = parse_blocks(&markdown_text);
+
+    blocks
+        .into_iter()
+        .map(|block| match block {
+            Block::Heading { level, text } => {
+                let copy_text = Signal::derive(move || text.clone());
+                let text_view = text.clone();
+                view! {
+                    <div class="md-block md-heading" data-level=level.to_string()>
+                        <strong>{text_view}</strong>
+                        <CopyButton text_to_copy=copy_text />
+                    </div>
+                }
+                .into_any()
+            }
+            Block::BlockQuote(text) => {
+                let copy_text = Signal::derive({
+                    let text = text.clone();
+                    move || text.clone()
+                });
+                view! {
+                    <div class="md-block">
+                        <blockquote class="md-blockquote">{render_inline(&text)}</blockquote>
+                        <CopyButton text_to_copy=copy_text />
+                    </div>
+                }
+                .into_any()
+            }
+            Block::ListItem { text } => {
+                view! {
+                    <ul class="md-block md-list">
+                        <li>{render_inline(&text)}</li>
+                    </ul>
+                }
+                .into_any()
+            }
+            Block::Code { lang, code } => {
+                let copy_text = Signal::derive({
+                    let code = code.clone();
+                    move || code.clone()
+                });
+                view! {
+                    <div class="md-block md-code">
+                        <div class="md-code-header">
+                            <span class="md-code-lang">{lang.clone().unwrap_or_default()}</span>
+                            <CopyButton text_to_copy=copy_text />
+                        </div>
+                        {render_code(&lang, &code)}
+                    </div>
+                }
+                .into_any()
+            }
+            Block::Paragraph(text) => {
+                let copy_text = Signal::derive({
+                    let text = text.clone();
+                    move || text.clone()
+                });
+                view! {
+                    <div class="md-block">
+                        <p class="md-paragraph">{render_inline(&text)}</p>
+                        <CopyButton text_to_copy=copy_text />
+                    </div>
+                }
+                .into_any()
+            }
+        })
+        .collect_view()
+}
@@ -0,0 +1,105 @@
+//! Web Notifications + tab-title unread badge for background streams.
+//!
+//! When a streamed response finishes (successfully, cancelled, or errored)
+//! while the tab is hidden, fire a notification and bump the title so the
+//! user can tell from the tab bar that something happened.
+
+use crate::dom_utils::{is_document_hidden, set_title};
+use leptos::logging::log;
+use leptos::prelude::window;
+use leptos::task::spawn_local;
+use std::cell::{Cell, RefCell};
+use wasm_bindgen::{closure::Closure, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Notification, NotificationOptions, NotificationPermission};
+
+thread_local! {
+    static UNREAD_COUNT: Cell<u32> = const { Cell::new(0) };
+    static BASE_TITLE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// How a streamed response ended, for picking the notification's wording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamOutcome {
+    Completed,
+    Cancelled,
+    Error,
+}
+
+fn base_title() -> String {
+    BASE_TITLE.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        if cell.is_none() {
+            *cell = Some(window().document().map(|d| d.title()).unwrap_or_default());
+        }
+        cell.clone().unwrap_or_default()
+    })
+}
+
+fn update_title_badge() {
+    let count = UNREAD_COUNT.with(Cell::get);
+    let base = base_title();
+    if count == 0 {
+        set_title(&base);
+    } else {
+        set_title(&format!("({count}) {base}"));
+    }
+}
+
+/// Installs a one-time `focus` listener that clears the unread badge when
+/// the user comes back to the tab. Call once at app startup.
+pub fn install_focus_listener() {
+    let closure = Closure::<dyn Fn()>::new(|| {
+        UNREAD_COUNT.with(|c| c.set(0));
+        update_title_badge();
+    });
+    if let Err(e) =
+        window().add_event_listener_with_callback("focus", closure.as_ref().unchecked_ref())
+    {
+        log!("[WARN] [Notifications] Failed to install focus listener: {e:?}");
+    }
+    closure.forget();
+}
+
+fn notification_text(model_name: &str, outcome: StreamOutcome, snippet: &str) -> (String, String) {
+    let title = match outcome {
+        StreamOutcome::Completed => format!("{model_name} replied"),
+        StreamOutcome::Cancelled => format!("{model_name} generation cancelled"),
+        StreamOutcome::Error => format!("{model_name} request failed"),
+    };
+    (title, snippet.to_string())
+}
+
+/// Fires a Web Notification and bumps the tab's unread badge if the
+/// document is hidden right now. Requests notification permission lazily
+/// the first time it's needed; if permission is denied (or still pending),
+/// this degrades silently and only the title badge updates.
+pub fn notify_if_hidden(model_name: &str, outcome: StreamOutcome, snippet: &str) {
+    if !is_document_hidden() {
+        return;
+    }
+
+    UNREAD_COUNT.with(|c| c.set(c.get() + 1));
+    update_title_badge();
+
+    match Notification::permission() {
+        NotificationPermission::Granted => {
+            let mut options = NotificationOptions::new();
+            options.set_body(snippet);
+            let (title, _) = notification_text(model_name, outcome, snippet);
+            if let Err(e) = Notification::new_with_options(&title, &options) {
+                log!("[WARN] [Notifications] Failed to show notification: {e:?}");
+            }
+        }
+        NotificationPermission::Default => {
+            // Ask now so a later completion can notify; this particular one
+            // is skipped since the browser prompt may not resolve in time.
+            spawn_local(async {
+                if let Ok(promise) = Notification::request_permission() {
+                    let _ = JsFuture::from(promise).await;
+                }
+            });
+        }
+        _ => {}
+    }
+}
@@ -3,29 +3,393 @@ use crate::chat::types::Message;
 
 // --- IndexedDB Constants ---
 pub const DB_NAME: &str = "tomatic_chat_db";
-pub const DB_VERSION: u32 = 1;
+pub const DB_VERSION: u32 = 6;
 pub const SESSIONS_STORE_NAME: &str = "chat_sessions";
 pub const SESSION_ID_KEY_PATH: &str = "session_id"; // Key path for the object store
 pub const UPDATED_AT_INDEX: &str = "updated_at_ms"; // Name for the index on updated_at_ms
+/// Compound index on `(updated_at_ms, session_id)`, so `get_session_page` can
+/// resume strictly after a `PageToken` without dropping or repeating rows
+/// that tie on `updated_at_ms` alone.
+pub const UPDATED_AT_SESSION_ID_INDEX: &str = "updated_at_ms_session_id";
+pub const PROMPTS_STORE_NAME: &str = "system_prompts";
+pub const PROMPT_ID_KEY_PATH: &str = "id";
+/// Inverted index (token -> session ids) backing `search_sessions`, kept in
+/// sync with `SESSIONS_STORE_NAME` by `save_session`/`delete_session`.
+pub const SEARCH_INDEX_STORE_NAME: &str = "search_index";
+pub const SEARCH_INDEX_TOKEN_KEY_PATH: &str = "token";
+/// Embedding vectors backing `crate::embeddings`' semantic retrieval, one
+/// record per message that's been embedded.
+pub const EMBEDDINGS_STORE_NAME: &str = "embeddings";
+pub const EMBEDDING_ID_KEY_PATH: &str = "message_id";
+/// Per-day-per-model cost rollups backing `crate::usage`, so totals don't
+/// require rehydrating every full session.
+pub const USAGE_ROLLUPS_STORE_NAME: &str = "usage_rollups";
+pub const USAGE_ROLLUP_KEY_PATH: &str = "rollup_key";
 
 // --- Chat Session Data Structure ---
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ChatSession {
     #[serde(rename = "session_id")] // Ensure JS side (IndexedDB) sees this as session_id
     pub session_id: String,
+    /// Every message across every branch this session has ever held, not
+    /// just the conversation currently on screen — regenerating a reply
+    /// keeps the one it's replacing as a sibling (see `Message::parent_id`)
+    /// instead of deleting it. `active_path` says which root-to-tip thread
+    /// through this tree is the one currently shown; `save_session` merges
+    /// callers' active-path-only messages into this tree by id rather than
+    /// overwriting it, so no branch is ever lost on save.
     pub messages: Vec<Message>,
+    /// Ids of the messages (root to tip) making up the conversation
+    /// currently shown, resolved against `messages` via `resolve_active_path`.
+    /// Empty for sessions saved before branching existed, in which case
+    /// `resolve_active_path` falls back to `messages` unchanged.
+    #[serde(default)]
+    pub active_path: Vec<String>,
     pub name: Option<String>, // User-defined name for the session
     pub created_at_ms: f64,
     pub updated_at_ms: f64,
+    /// When set, the session is soft-deleted (in the trash) and will be
+    /// permanently purged after [`TRASH_GRACE_PERIOD_MS`] by `purge_expired_trash`.
+    #[serde(default)]
+    pub deleted_at_ms: Option<f64>,
+}
+
+/// Resolves the conversation currently shown for `session`: the messages
+/// named by `active_path`, in order. Falls back to `session.messages`
+/// unchanged when `active_path` is empty (legacy sessions saved before
+/// branching existed), so no explicit schema migration is needed — they
+/// self-heal the next time they're saved.
+pub fn resolve_active_path(session: &ChatSession) -> Vec<Message> {
+    if session.active_path.is_empty() {
+        return session.messages.clone();
+    }
+    let by_id: HashMap<&str, &Message> =
+        session.messages.iter().map(|m| (m.id.as_str(), m)).collect();
+    session
+        .active_path
+        .iter()
+        .filter_map(|id| by_id.get(id.as_str()).map(|m| (*m).clone()))
+        .collect()
+}
+
+/// Merges `active` into `existing` by message id: updates any message also
+/// present in `existing` (e.g. a reply whose content grew while streaming)
+/// and appends any message in `active` that `existing` doesn't have yet.
+/// Never removes a message, so draining the live UI's active-path vec
+/// during a regenerate can never delete the branch it replaced on disk.
+pub fn merge_into_tree(existing: &[Message], active: &[Message]) -> Vec<Message> {
+    let mut merged = existing.to_vec();
+    for message in active {
+        match merged.iter_mut().find(|m| m.id == message.id) {
+            Some(slot) => *slot = message.clone(),
+            None => merged.push(message.clone()),
+        }
+    }
+    merged
+}
+
+/// The direct children of `parent_id` within `tree` (or the root messages,
+/// for `parent_id: None`), in tree order.
+pub fn children_of<'a>(tree: &'a [Message], parent_id: Option<&str>) -> Vec<&'a Message> {
+    tree.iter()
+        .filter(|m| m.parent_id.as_deref() == parent_id)
+        .collect()
+}
+
+/// The ids from `tree`'s root down to `message_id`, inclusive. Empty if
+/// `message_id` isn't in `tree`.
+pub fn path_to_root(tree: &[Message], message_id: &str) -> Vec<String> {
+    let by_id: HashMap<&str, &Message> = tree.iter().map(|m| (m.id.as_str(), m)).collect();
+    let mut path = Vec::new();
+    let mut current = by_id.get(message_id).copied();
+    while let Some(message) = current {
+        path.push(message.id.clone());
+        current = message
+            .parent_id
+            .as_deref()
+            .and_then(|id| by_id.get(id).copied());
+    }
+    path.reverse();
+    path
+}
+
+/// Extends `path` down to a leaf by repeatedly following the most recently
+/// added child, so switching to a branch shows its latest reply rather than
+/// stopping at the fork point.
+pub fn extend_to_leaf(tree: &[Message], path: &mut Vec<String>) {
+    while let Some(last_id) = path.last().cloned() {
+        let Some(last_child) = children_of(tree, Some(last_id.as_str())).into_iter().last() else {
+            break;
+        };
+        path.push(last_child.id.clone());
+    }
+}
+
+/// The full active path for the branch containing `message_id`: root down
+/// to it, then extended to that branch's leaf.
+pub fn switch_branch_path(tree: &[Message], message_id: &str) -> Vec<String> {
+    let mut path = path_to_root(tree, message_id);
+    extend_to_leaf(tree, &mut path);
+    path
+}
+
+/// Every alternate of `message_id` — the messages sharing its `parent_id`,
+/// `message_id` included — in tree order. The choices a "‹ i/n ›" branch
+/// switcher offers.
+pub fn siblings_of<'a>(tree: &'a [Message], message_id: &str) -> Vec<&'a Message> {
+    let Some(message) = tree.iter().find(|m| m.id == message_id) else {
+        return Vec::new();
+    };
+    children_of(tree, message.parent_id.as_deref())
+}
+
+/// Alias for [`siblings_of`] under the name a caller reaching for "what
+/// branches exist here" would look for first.
+pub fn list_branches<'a>(tree: &'a [Message], message_id: &str) -> Vec<&'a Message> {
+    siblings_of(tree, message_id)
+}
+
+/// Appends `message` (with `parent_id` set as given) to `session_id`'s
+/// persisted tree and makes it the new tip of the active path, returning
+/// its id — already generated by the caller, see
+/// [`crate::chat::types::new_message_id`] — as the node id `list_branches`
+/// and `switch_branch_path` key off of. Creates the session record if it
+/// doesn't exist yet.
+///
+/// This is a granular alternative to [`save_session`] for a caller that has
+/// one new message rather than a whole rebuilt conversation. The live chat
+/// UI doesn't use it today — `ChatInterface`'s debounced whole-session save
+/// (see `main.rs`) remains the normal path — but it lets a future caller
+/// (an import job, a background agent writing one reply at a time) persist
+/// incrementally without holding the rest of the conversation in memory.
+pub async fn save_message(
+    session_id: &str,
+    parent_id: Option<&str>,
+    mut message: Message,
+    now_ms: f64,
+) -> Result<String> {
+    message.parent_id = parent_id.map(str::to_string);
+    let node_id = message.id.clone();
+
+    let previous = load_session(session_id).await?;
+    let active_path = match (&previous, parent_id) {
+        (Some(prev), Some(pid)) => {
+            let mut path = path_to_root(&prev.messages, pid);
+            path.push(node_id.clone());
+            path
+        }
+        _ => vec![node_id.clone()],
+    };
+
+    let session = ChatSession {
+        session_id: session_id.to_string(),
+        messages: vec![message],
+        active_path,
+        name: previous.as_ref().and_then(|s| s.name.clone()),
+        created_at_ms: previous.as_ref().map_or(now_ms, |s| s.created_at_ms),
+        updated_at_ms: now_ms,
+        deleted_at_ms: previous.and_then(|s| s.deleted_at_ms),
+    };
+    save_session(&session).await?;
+    Ok(node_id)
+}
+
+// --- System Prompt Library Data Structure ---
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PromptRecord {
+    pub id: String,
+    pub title: String,
+    pub body: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Optional longer description, e.g. from a Markdown frontmatter
+    /// `description:` field — see `prompt_library::parse_markdown_prompt`.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Default values for `{{variable}}` placeholders in `body`, carried
+    /// through to `chat::types::SystemPrompt::variables` for `@mention`
+    /// expansion. `#[serde(default)]` backfills an empty list for records
+    /// written before this field existed.
+    #[serde(default)]
+    pub variables: Vec<(String, String)>,
+    pub created_at_ms: f64,
+    pub updated_at_ms: f64,
+    /// Shape version, so records written by older client versions can be
+    /// forward-migrated on load instead of silently truncated. Missing on
+    /// records written before this field existed, which `serde(default)`
+    /// reads as `0`. See `migrate_prompt_record`.
+    #[serde(default)]
+    pub schema_version: u32,
 }
 
 use anyhow::{anyhow, Result};
-use idb::{Database, event::VersionChangeEvent, Error as IdbError, Factory, IndexParams, KeyPath, ObjectStoreParams, TransactionMode, DatabaseEvent, Event, Request, CursorDirection};
+use idb::{Database, event::VersionChangeEvent, Error as IdbError, Factory, IndexParams, KeyPath, ObjectStore, ObjectStoreParams, TransactionMode, DatabaseEvent, Event, Request, CursorDirection};
+use std::collections::{HashMap, HashSet};
 use wasm_bindgen::JsValue;
+use web_sys::js_sys::Array;
+
+/// An entry in the `search_index` store: a lowercased token and the ids of
+/// every session whose content contains it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SearchIndexEntry {
+    token: String,
+    session_ids: Vec<String>,
+}
+
+/// A session whose content matched a `search_sessions` query, with enough
+/// metadata and snippet text to render a result list without loading the
+/// full session record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionMatch {
+    pub session_id: String,
+    pub name: Option<String>,
+    pub updated_at_ms: f64,
+    pub snippets: Vec<String>,
+}
+
+/// Lowercases and splits `text` into alphanumeric tokens, dropping anything
+/// shorter than 2 chars (punctuation, stray single letters).
+fn tokenize(text: &str) -> HashSet<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| token.len() >= 2)
+        .map(|token| token.to_string())
+        .collect()
+}
+
+/// All searchable tokens for a session: its name plus every message's content.
+fn session_tokens(session: &ChatSession) -> HashSet<String> {
+    let mut tokens = HashSet::new();
+    if let Some(name) = &session.name {
+        tokens.extend(tokenize(name));
+    }
+    for message in &session.messages {
+        tokens.extend(tokenize(&message.content));
+    }
+    tokens
+}
 
 // --- Database Interaction Functions ---
 
-/// Opens the IndexedDB database and creates/upgrades the object store and indexes.
+/// One schema migration step, run within the upgrade transaction for every
+/// version between a client's current version and [`DB_VERSION`]. Steps are
+/// 1-indexed by target version (`MIGRATIONS[0]` upgrades to v1, `MIGRATIONS[1]`
+/// to v2, ...) and must each be self-contained (create a store, add an
+/// index, backfill a field) so upgrading through several versions at once
+/// just runs the intermediate steps in order instead of skipping any.
+type Migration = fn(&Database, &idb::Transaction) -> Result<(), IdbError>;
+
+const MIGRATIONS: &[Migration] =
+    &[migrate_to_v1, migrate_to_v2, migrate_to_v3, migrate_to_v4, migrate_to_v5, migrate_to_v6];
+
+/// v1: the original `chat_sessions` store, indexed by `updated_at_ms` so
+/// sessions can be listed newest-first without a full scan.
+fn migrate_to_v1(db: &Database, _tx: &idb::Transaction) -> Result<(), IdbError> {
+    if db.store_names().iter().any(|name| *name == SESSIONS_STORE_NAME) {
+        return Ok(());
+    }
+    leptos::logging::log!("[INFO] [DB] Creating object store: {}", SESSIONS_STORE_NAME);
+    let mut store_params = ObjectStoreParams::new();
+    store_params.key_path(Some(KeyPath::new_single(SESSION_ID_KEY_PATH)));
+    let store = db.create_object_store(SESSIONS_STORE_NAME, store_params)?;
+
+    leptos::logging::log!("[INFO] [DB] Creating index '{}' on store '{}'", UPDATED_AT_INDEX, SESSIONS_STORE_NAME);
+    let mut index_params = IndexParams::new();
+    index_params.unique(false); // updated_at_ms might not be unique
+    store.create_index(UPDATED_AT_INDEX, KeyPath::new_single("updated_at_ms"), Some(index_params))?;
+    Ok(())
+}
+
+/// v2: adds the `system_prompts` store (see [`PromptRecord`]), and backfills
+/// the `updated_at_ms` index for any `chat_sessions` store that predates it.
+fn migrate_to_v2(db: &Database, tx: &idb::Transaction) -> Result<(), IdbError> {
+    if let Ok(store) = tx.object_store(SESSIONS_STORE_NAME) {
+        if !store.index_names().iter().any(|name| name == UPDATED_AT_INDEX) {
+            leptos::logging::log!("[INFO] [DB] Store '{}' exists, creating missing index: {}", SESSIONS_STORE_NAME, UPDATED_AT_INDEX);
+            let mut index_params = IndexParams::new();
+            index_params.unique(false);
+            store.create_index(UPDATED_AT_INDEX, KeyPath::new_single("updated_at_ms"), Some(index_params))?;
+        }
+    }
+
+    if !db.store_names().iter().any(|name| *name == PROMPTS_STORE_NAME) {
+        leptos::logging::log!("[INFO] [DB] Creating object store: {}", PROMPTS_STORE_NAME);
+        let mut store_params = ObjectStoreParams::new();
+        store_params.key_path(Some(KeyPath::new_single(PROMPT_ID_KEY_PATH)));
+        db.create_object_store(PROMPTS_STORE_NAME, store_params)?;
+    }
+    Ok(())
+}
+
+/// v3: adds the `search_index` store backing `search_sessions`.
+fn migrate_to_v3(db: &Database, _tx: &idb::Transaction) -> Result<(), IdbError> {
+    if db.store_names().iter().any(|name| *name == SEARCH_INDEX_STORE_NAME) {
+        return Ok(());
+    }
+    leptos::logging::log!("[INFO] [DB] Creating object store: {}", SEARCH_INDEX_STORE_NAME);
+    let mut store_params = ObjectStoreParams::new();
+    store_params.key_path(Some(KeyPath::new_single(SEARCH_INDEX_TOKEN_KEY_PATH)));
+    db.create_object_store(SEARCH_INDEX_STORE_NAME, store_params)?;
+    Ok(())
+}
+
+/// v4: adds the `embeddings` store backing `crate::embeddings`' semantic
+/// retrieval, keyed by the id of the message each vector was computed from.
+fn migrate_to_v4(db: &Database, _tx: &idb::Transaction) -> Result<(), IdbError> {
+    if db.store_names().iter().any(|name| *name == EMBEDDINGS_STORE_NAME) {
+        return Ok(());
+    }
+    leptos::logging::log!("[INFO] [DB] Creating object store: {}", EMBEDDINGS_STORE_NAME);
+    let mut store_params = ObjectStoreParams::new();
+    store_params.key_path(Some(KeyPath::new_single(EMBEDDING_ID_KEY_PATH)));
+    db.create_object_store(EMBEDDINGS_STORE_NAME, store_params)?;
+    Ok(())
+}
+
+/// v5: adds the `usage_rollups` store backing `crate::usage`, keyed by the
+/// derived `"{date}|{model_name}"` string (see [`UsageRollup::rollup_key`]).
+fn migrate_to_v5(db: &Database, _tx: &idb::Transaction) -> Result<(), IdbError> {
+    if db.store_names().iter().any(|name| *name == USAGE_ROLLUPS_STORE_NAME) {
+        return Ok(());
+    }
+    leptos::logging::log!("[INFO] [DB] Creating object store: {}", USAGE_ROLLUPS_STORE_NAME);
+    let mut store_params = ObjectStoreParams::new();
+    store_params.key_path(Some(KeyPath::new_single(USAGE_ROLLUP_KEY_PATH)));
+    db.create_object_store(USAGE_ROLLUPS_STORE_NAME, store_params)?;
+    Ok(())
+}
+
+/// v6: adds the `UPDATED_AT_SESSION_ID_INDEX` compound index on
+/// `chat_sessions` so `get_session_page` has a tiebreaker on `session_id`
+/// when two sessions share an `updated_at_ms`, instead of silently dropping
+/// one at a page boundary.
+fn migrate_to_v6(_db: &Database, tx: &idb::Transaction) -> Result<(), IdbError> {
+    if let Ok(store) = tx.object_store(SESSIONS_STORE_NAME) {
+        if !store.index_names().iter().any(|name| name == UPDATED_AT_SESSION_ID_INDEX) {
+            leptos::logging::log!(
+                "[INFO] [DB] Creating compound index '{}' on store '{}'",
+                UPDATED_AT_SESSION_ID_INDEX,
+                SESSIONS_STORE_NAME
+            );
+            let mut index_params = IndexParams::new();
+            index_params.unique(false);
+            store.create_index(
+                UPDATED_AT_SESSION_ID_INDEX,
+                KeyPath::new_array(vec!["updated_at_ms", SESSION_ID_KEY_PATH]),
+                Some(index_params),
+            )?;
+        }
+    } else {
+        leptos::logging::log!(
+            "[WARN] [DB] migrate_to_v6: '{}' store not found, skipping compound index creation.",
+            SESSIONS_STORE_NAME
+        );
+    }
+    Ok(())
+}
+
+/// Opens the IndexedDB database, running every migration between the
+/// client's stored version and [`DB_VERSION`] in order.
 pub async fn get_db() -> Result<Database, IdbError> {
     let factory = Factory::new()?;
 
@@ -39,54 +403,28 @@ pub async fn get_db() -> Result<Database, IdbError> {
                 return; // Cannot proceed with upgrade.
             }
         };
-
-        // Create object store if it doesn't exist
-        if !db.store_names().iter().any(|name| *name == SESSIONS_STORE_NAME) {
-            leptos::logging::log!("[INFO] [DB] Creating object store: {}", SESSIONS_STORE_NAME);
-            let mut store_params = ObjectStoreParams::new();
-            store_params.key_path(Some(KeyPath::new_single(SESSION_ID_KEY_PATH)));
-            match db.create_object_store(SESSIONS_STORE_NAME, store_params) {
-                Ok(store) => {
-                    // Create index on the new store
-                    leptos::logging::log!("[INFO] [DB] Creating index '{}' on store '{}'", UPDATED_AT_INDEX, SESSIONS_STORE_NAME);
-                    let mut index_params = IndexParams::new();
-                    index_params.unique(false); // updated_at_ms might not be unique
-                    if let Err(e) = store.create_index(UPDATED_AT_INDEX, KeyPath::new_single("updated_at_ms"), Some(index_params)) {
-                        leptos::logging::log!("[ERROR] [DB] Failed to create index '{}' on store '{}': {:?}", UPDATED_AT_INDEX, SESSIONS_STORE_NAME, e);
-                    }
-                }
-                Err(e) => {
-                    leptos::logging::log!("[ERROR] [DB] Failed to create object store '{}': {:?}", SESSIONS_STORE_NAME, e);
-                    // If store creation fails, we can't create indexes on it.
-                }
+        let transaction = match event.target() {
+            Ok(open_db_request) => open_db_request.transaction(),
+            Err(e) => {
+                leptos::logging::log!("[ERROR] [DB] Failed to get OpenDbRequest event target during upgrade: {:?}", e);
+                None
             }
-        } else {
-            // Store exists, check if index needs to be created (e.g., upgrading from a version without it)
-            // This requires getting the transaction from the upgrade event.
-            match event.target() {
-                Ok(open_db_request) => { // open_db_request is idb::request::OpenDbRequest
-                    if let Some(transaction) = open_db_request.transaction() { // transaction is idb::Transaction
-                        match transaction.object_store(SESSIONS_STORE_NAME) {
-                            Ok(store) => {
-                                if !store.index_names().iter().any(|name| name == UPDATED_AT_INDEX) {
-                                    leptos::logging::log!("[INFO] [DB] Store '{}' exists, creating missing index: {}", SESSIONS_STORE_NAME, UPDATED_AT_INDEX);
-                                    let mut index_params = IndexParams::new();
-                                    index_params.unique(false);
-                                    if let Err(e) = store.create_index(UPDATED_AT_INDEX, KeyPath::new_single("updated_at_ms"), Some(index_params)) {
-                                        leptos::logging::log!("[ERROR] [DB] Failed to create index '{}' on existing store '{}': {:?}", UPDATED_AT_INDEX, SESSIONS_STORE_NAME, e);
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                leptos::logging::log!("[ERROR] [DB] Failed to get object store '{}' from upgrade transaction: {:?}", SESSIONS_STORE_NAME, e);
-                            }
-                        }
-                    } else {
-                        leptos::logging::log!("[WARN] [DB] Upgrade transaction was None from OpenDbRequest.");
+        };
+        let Some(transaction) = transaction else {
+            leptos::logging::log!("[WARN] [DB] Upgrade transaction was None from OpenDbRequest.");
+            return;
+        };
+
+        let old_version = event.old_version() as usize;
+        for version in (old_version + 1)..=(DB_VERSION as usize) {
+            match MIGRATIONS.get(version - 1) {
+                Some(migration) => {
+                    if let Err(e) = migration(&db, &transaction) {
+                        leptos::logging::log!("[ERROR] [DB] Migration to v{}: {:?}", version, e);
                     }
                 }
-                Err(e) => {
-                    leptos::logging::log!("[ERROR] [DB] Failed to get OpenDbRequest event target during upgrade: {:?}", e);
+                None => {
+                    leptos::logging::log!("[WARN] [DB] No migration registered for version {}", version);
                 }
             }
         }
@@ -96,11 +434,106 @@ pub async fn get_db() -> Result<Database, IdbError> {
     open_request.await
 }
 
-/// Saves (adds or updates) a chat session in IndexedDB.
+/// Adds `session_id` to the posting list for `token`, creating the entry if
+/// this is the first session to contain it.
+async fn add_session_to_posting_list(store: &ObjectStore, token: &str, session_id: &str) -> Result<()> {
+    let key_js_value = JsValue::from_str(token);
+    let existing: Option<JsValue> = store
+        .get(idb::Query::from(key_js_value))
+        .map_err(|e| anyhow!("[DB] SearchIndex: Failed to initiate get for token '{}' (sync): {}", token, e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] SearchIndex: Failed to get token '{}' (async): {}", token, e.to_string()))?;
+
+    let mut entry: SearchIndexEntry = match existing {
+        Some(js_value) => serde_wasm_bindgen::from_value(js_value)
+            .map_err(|e| anyhow!("[DB] SearchIndex: Failed to deserialize entry for token '{}': {}", token, e.to_string()))?,
+        None => SearchIndexEntry { token: token.to_string(), session_ids: Vec::new() },
+    };
+    if !entry.session_ids.iter().any(|id| id == session_id) {
+        entry.session_ids.push(session_id.to_string());
+    }
+
+    let js_value = serde_wasm_bindgen::to_value(&entry)
+        .map_err(|e| anyhow!("[DB] SearchIndex: Failed to serialize entry for token '{}': {}", token, e.to_string()))?;
+    store
+        .put(&js_value, None)
+        .map_err(|e| anyhow!("[DB] SearchIndex: Failed to put entry for token '{}' (sync): {}", token, e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] SearchIndex: Failed to put entry for token '{}' (async): {}", token, e.to_string()))?;
+    Ok(())
+}
+
+/// Removes `session_id` from the posting list for `token`, deleting the
+/// entry entirely once its posting list is empty.
+async fn remove_session_from_posting_list(store: &ObjectStore, token: &str, session_id: &str) -> Result<()> {
+    let key_js_value = JsValue::from_str(token);
+    let existing: Option<JsValue> = store
+        .get(idb::Query::from(key_js_value.clone()))
+        .map_err(|e| anyhow!("[DB] SearchIndex: Failed to initiate get for token '{}' (sync): {}", token, e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] SearchIndex: Failed to get token '{}' (async): {}", token, e.to_string()))?;
+
+    let Some(js_value) = existing else {
+        return Ok(());
+    };
+    let mut entry: SearchIndexEntry = serde_wasm_bindgen::from_value(js_value)
+        .map_err(|e| anyhow!("[DB] SearchIndex: Failed to deserialize entry for token '{}': {}", token, e.to_string()))?;
+    entry.session_ids.retain(|id| id != session_id);
+
+    if entry.session_ids.is_empty() {
+        store
+            .delete(idb::Query::from(key_js_value))
+            .map_err(|e| anyhow!("[DB] SearchIndex: Failed to initiate delete for token '{}' (sync): {}", token, e.to_string()))?
+            .await
+            .map_err(|e| anyhow!("[DB] SearchIndex: Failed to delete token '{}' (async): {}", token, e.to_string()))?;
+    } else {
+        let js_value = serde_wasm_bindgen::to_value(&entry)
+            .map_err(|e| anyhow!("[DB] SearchIndex: Failed to serialize entry for token '{}': {}", token, e.to_string()))?;
+        store
+            .put(&js_value, None)
+            .map_err(|e| anyhow!("[DB] SearchIndex: Failed to put entry for token '{}' (sync): {}", token, e.to_string()))?
+            .await
+            .map_err(|e| anyhow!("[DB] SearchIndex: Failed to put entry for token '{}' (async): {}", token, e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Saves (adds or updates) a chat session in IndexedDB, and keeps the
+/// `search_index` posting lists in sync by diffing this session's old and
+/// new token sets.
+///
+/// `session.messages` only needs to hold the conversation currently on
+/// screen (the active path) — it's merged by id (via [`merge_into_tree`])
+/// into whatever full branch tree is already stored, so regenerating a
+/// reply never drops the branch it replaced. `session.active_path` is
+/// derived from `session.messages`'s ids if the caller left it empty.
 pub async fn save_session(session: &ChatSession) -> Result<()> {
+    let previous = load_session(&session.session_id).await.unwrap_or(None);
+
+    let merged_messages = match &previous {
+        Some(prev) => merge_into_tree(&prev.messages, &session.messages),
+        None => session.messages.clone(),
+    };
+    let active_path = if session.active_path.is_empty() {
+        session.messages.iter().map(|m| m.id.clone()).collect()
+    } else {
+        session.active_path.clone()
+    };
+    let session = &ChatSession {
+        messages: merged_messages,
+        active_path,
+        ..session.clone()
+    };
+
+    let old_tokens = previous
+        .as_ref()
+        .map(session_tokens)
+        .unwrap_or_default();
+    let new_tokens = session_tokens(session);
+
     let db = get_db().await.map_err(|e| anyhow!("[DB] Save: DB open error: {}", e.to_string()))?;
     let tx = db
-        .transaction(&[SESSIONS_STORE_NAME], TransactionMode::ReadWrite)
+        .transaction(&[SESSIONS_STORE_NAME, SEARCH_INDEX_STORE_NAME], TransactionMode::ReadWrite)
         .map_err(|e| anyhow!("[DB] Save: Failed to start transaction: {}", e.to_string()))?;
     let store = tx
         .object_store(SESSIONS_STORE_NAME)
@@ -117,6 +550,16 @@ pub async fn save_session(session: &ChatSession) -> Result<()> {
         .await // Wait for the put operation itself to complete
         .map_err(|e| anyhow!("[DB] Save: Failed to put session (async): {}", e.to_string()))?;
 
+    let index_store = tx
+        .object_store(SEARCH_INDEX_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] Save: Failed to get search index store: {}", e.to_string()))?;
+    for token in new_tokens.difference(&old_tokens) {
+        add_session_to_posting_list(&index_store, token, &session.session_id).await?;
+    }
+    for token in old_tokens.difference(&new_tokens) {
+        remove_session_from_posting_list(&index_store, token, &session.session_id).await?;
+    }
+
     tx.commit() // Commit the transaction
         .map_err(|e| anyhow!("[DB] Save: Failed to initiate commit (sync): {}", e.to_string()))?
         .await // Wait for commit to complete
@@ -126,6 +569,118 @@ pub async fn save_session(session: &ChatSession) -> Result<()> {
     Ok(())
 }
 
+/// Finds the largest byte offset `<= index` that lands on a UTF-8 char
+/// boundary, so snippet slicing never panics on a multi-byte character.
+fn floor_char_boundary(s: &str, index: usize) -> usize {
+    let mut index = index.min(s.len());
+    while index > 0 && !s.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Finds the smallest byte offset `>= index` that lands on a UTF-8 char boundary.
+fn ceil_char_boundary(s: &str, index: usize) -> usize {
+    let mut index = index.min(s.len());
+    while index < s.len() && !s.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+/// Pulls up to 3 short snippets of surrounding context from the first
+/// messages whose content contains one of `terms`, for display in search results.
+fn extract_snippets(session: &ChatSession, terms: &[String]) -> Vec<String> {
+    const SNIPPET_RADIUS: usize = 60;
+    const MAX_SNIPPETS: usize = 3;
+
+    let mut snippets = Vec::new();
+    for message in &session.messages {
+        let lower = message.content.to_lowercase();
+        if let Some(pos) = terms.iter().find_map(|term| lower.find(term.as_str())) {
+            let start = floor_char_boundary(&message.content, pos.saturating_sub(SNIPPET_RADIUS));
+            let end = ceil_char_boundary(&message.content, (pos + SNIPPET_RADIUS).min(message.content.len()));
+            snippets.push(message.content[start..end].trim().to_string());
+        }
+        if snippets.len() >= MAX_SNIPPETS {
+            break;
+        }
+    }
+    snippets
+}
+
+/// Finds sessions whose messages (or name) contain every (case-folded) term
+/// in `query`. Looks up each term's posting list in `search_index` and
+/// intersects them before loading only the candidate sessions, so this
+/// doesn't deserialize the whole sessions store on every keystroke.
+pub async fn search_sessions(query: &str) -> Result<Vec<SessionMatch>> {
+    let terms: Vec<String> = tokenize(query).into_iter().collect();
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let db = get_db().await.map_err(|e| anyhow!("[DB] Search: DB open error: {}", e.to_string()))?;
+    let tx = db
+        .transaction(&[SEARCH_INDEX_STORE_NAME], TransactionMode::ReadOnly)
+        .map_err(|e| anyhow!("[DB] Search: Failed to start transaction: {}", e.to_string()))?;
+    let store = tx
+        .object_store(SEARCH_INDEX_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] Search: Failed to get search index store: {}", e.to_string()))?;
+
+    let mut candidate_ids: Option<HashSet<String>> = None;
+    for term in &terms {
+        let key_js_value = JsValue::from_str(term);
+        let js_value_opt: Option<JsValue> = store
+            .get(idb::Query::from(key_js_value))
+            .map_err(|e| anyhow!("[DB] Search: Failed to initiate get for term '{}' (sync): {}", term, e.to_string()))?
+            .await
+            .map_err(|e| anyhow!("[DB] Search: Failed to get term '{}' (async): {}", term, e.to_string()))?;
+
+        let posting_list: HashSet<String> = match js_value_opt {
+            Some(js_value) => {
+                let entry: SearchIndexEntry = serde_wasm_bindgen::from_value(js_value)
+                    .map_err(|e| anyhow!("[DB] Search: Failed to deserialize entry for term '{}': {}", term, e.to_string()))?;
+                entry.session_ids.into_iter().collect()
+            }
+            None => HashSet::new(),
+        };
+
+        candidate_ids = Some(match candidate_ids {
+            Some(existing) => existing.intersection(&posting_list).cloned().collect(),
+            None => posting_list,
+        });
+
+        if candidate_ids.as_ref().is_some_and(HashSet::is_empty) {
+            break;
+        }
+    }
+
+    tx.await
+        .map_err(|e| anyhow!("[DB] Search: Transaction completion error: {}", e.to_string()))?;
+
+    let candidate_ids = candidate_ids.unwrap_or_default();
+    let mut matches = Vec::new();
+    for session_id in candidate_ids {
+        if let Some(session) = load_session(&session_id).await? {
+            let snippets = extract_snippets(&session, &terms);
+            matches.push(SessionMatch {
+                session_id: session.session_id,
+                name: session.name,
+                updated_at_ms: session.updated_at_ms,
+                snippets,
+            });
+        }
+    }
+    matches.sort_by(|a, b| {
+        b.updated_at_ms
+            .partial_cmp(&a.updated_at_ms)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    leptos::logging::log!("[DEBUG] [DB] Search for '{}' matched {} sessions.", query, matches.len());
+    Ok(matches)
+}
+
 /// Loads a chat session from IndexedDB by its ID.
 pub async fn load_session(session_id: &str) -> Result<Option<ChatSession>> {
     let db = get_db().await.map_err(|e| anyhow!("[DB] Load: DB open error: {}", e.to_string()))?;
@@ -206,13 +761,218 @@ pub async fn get_all_session_keys_sorted_by_update() -> Result<Vec<String>> {
     Ok(keys)
 }
 
+/// Resume point for `get_session_page`: the `(updated_at_ms, session_id)` of
+/// the last key returned by the previous page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageToken {
+    pub updated_at_ms: f64,
+    pub session_id: String,
+}
+
+/// One page of session ids from `get_session_page`, plus a token to fetch
+/// the next page if `next` is `Some`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionPage {
+    pub keys: Vec<String>,
+    pub next: Option<PageToken>,
+}
+
+/// Loads up to `limit` session ids ordered by `updated_at_ms` descending
+/// (newest first), resuming strictly after `after` if given. Used by the
+/// sidebar to lazily load older conversations on scroll instead of
+/// materializing every session key up front like
+/// `get_all_session_keys_sorted_by_update` does.
+pub async fn get_session_page(after: Option<PageToken>, limit: usize) -> Result<SessionPage> {
+    let db = get_db().await.map_err(|e| anyhow!("[DB] Page: DB open error: {}", e.to_string()))?;
+    let tx = db
+        .transaction(&[SESSIONS_STORE_NAME], TransactionMode::ReadOnly)
+        .map_err(|e| anyhow!("[DB] Page: Failed to start transaction: {}", e.to_string()))?;
+    let store = tx
+        .object_store(SESSIONS_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] Page: Failed to get object store: {}", e.to_string()))?;
+    let index = store
+        .index(UPDATED_AT_SESSION_ID_INDEX)
+        .map_err(|e| anyhow!("[DB] Page: Failed to get index: {}", e.to_string()))?;
+
+    // Resume strictly before the last-seen `(updated_at_ms, session_id)` pair.
+    // Array keys compare lexicographically (first by `updated_at_ms`, then by
+    // `session_id`), so an exclusive upper bound on the exact pair resumes
+    // right after it without dropping or repeating sessions that tie on
+    // `updated_at_ms` alone.
+    let query = match &after {
+        Some(token) => {
+            let bound = Array::new();
+            bound.push(&JsValue::from_f64(token.updated_at_ms));
+            bound.push(&JsValue::from_str(&token.session_id));
+            let range = idb::KeyRange::upper_bound(&bound.into(), true)
+                .map_err(|e| anyhow!("[DB] Page: Failed to build key range: {}", e.to_string()))?;
+            Some(idb::Query::from(range))
+        }
+        None => None,
+    };
+
+    let mut cursor = index
+        .open_cursor(query, Some(CursorDirection::Prev))
+        .map_err(|e| anyhow!("[DB] Page: Failed to open cursor (sync): {}", e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] Page: Failed to open cursor (async): {}", e.to_string()))?;
+
+    let mut keys = Vec::new();
+    let mut last_seen: Option<(f64, String)> = None;
+    let mut has_more = false;
+
+    loop {
+        let Some(c) = cursor else { break };
+        if keys.len() >= limit {
+            has_more = true;
+            break;
+        }
+        match (c.primary_key(), c.key()) {
+            (Ok(primary_key_js), Ok(index_key_js)) => {
+                let updated_at_ms = Array::from(&index_key_js).get(0).as_f64();
+                if let (Some(key_str), Some(updated_at_ms)) =
+                    (primary_key_js.as_string(), updated_at_ms)
+                {
+                    keys.push(key_str.clone());
+                    last_seen = Some((updated_at_ms, key_str));
+                } else {
+                    leptos::logging::log!("[WARN] [DB] Page: Cursor record with non-string key or non-numeric index value.");
+                }
+            }
+            _ => leptos::logging::log!("[WARN] [DB] Page: Error reading cursor key/primary key."),
+        }
+        cursor = c
+            .next(None)
+            .map_err(|e| anyhow!("[DB] Page: Failed to initiate next (sync): {}", e.to_string()))?
+            .await
+            .map_err(|e| anyhow!("[DB] Page: Error advancing cursor (async): {}", e.to_string()))?;
+    }
+
+    tx.await
+        .map_err(|e| anyhow!("[DB] Page: Transaction completion error: {}", e.to_string()))?;
+
+    let next = has_more
+        .then(|| last_seen.map(|(updated_at_ms, session_id)| PageToken { updated_at_ms, session_id }))
+        .flatten();
+
+    leptos::logging::log!("[DEBUG] [DB] Fetched a page of {} session keys.", keys.len());
+    Ok(SessionPage { keys, next })
+}
+
+
+/// Loads all chat sessions (full records, not just keys), sorted by
+/// `updated_at_ms` descending. Used by the session switcher to fuzzy-search
+/// over session names and content.
+pub async fn load_all_sessions() -> Result<Vec<ChatSession>> {
+    let db = get_db().await.map_err(|e| anyhow!("[DB] ListAll: DB open error: {}", e.to_string()))?;
+    let tx = db
+        .transaction(&[SESSIONS_STORE_NAME], TransactionMode::ReadOnly)
+        .map_err(|e| anyhow!("[DB] ListAll: Failed to start transaction: {}", e.to_string()))?;
+    let store = tx
+        .object_store(SESSIONS_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] ListAll: Failed to get object store: {}", e.to_string()))?;
+    let index = store
+        .index(UPDATED_AT_INDEX)
+        .map_err(|e| anyhow!("[DB] ListAll: Failed to get index: {}", e.to_string()))?;
+
+    let mut cursor = index
+        .open_cursor(None, Some(CursorDirection::Prev))
+        .map_err(|e| anyhow!("[DB] ListAll: Failed to open cursor (sync): {}", e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] ListAll: Failed to open cursor (async): {}", e.to_string()))?;
+
+    let mut sessions = Vec::new();
+    while let Some(c) = cursor {
+        match c.value() {
+            Ok(js_value) => match serde_wasm_bindgen::from_value::<ChatSession>(js_value) {
+                Ok(session) => sessions.push(session),
+                Err(e) => leptos::logging::log!("[WARN] [DB] ListAll: Failed to deserialize session: {:?}", e),
+            },
+            Err(e) => leptos::logging::log!("[WARN] [DB] ListAll: Error getting value from cursor: {:?}", e),
+        }
+        cursor = c.next(None)
+            .map_err(|e| anyhow!("[DB] ListAll: Failed to initiate next (sync): {}", e.to_string()))?
+            .await
+            .map_err(|e| anyhow!("[DB] ListAll: Error advancing cursor (async): {}", e.to_string()))?;
+    }
+
+    tx.await
+        .map_err(|e| anyhow!("[DB] ListAll: Transaction completion error: {}", e.to_string()))?;
+
+    leptos::logging::log!("[DEBUG] [DB] Fetched {} full sessions.", sessions.len());
+    Ok(sessions)
+}
+
+/// Renames a session, writing the updated record back to IndexedDB.
+pub async fn rename_session(session_id: &str, name: String) -> Result<()> {
+    let mut session = load_session(session_id)
+        .await?
+        .ok_or_else(|| anyhow!("[DB] Rename: Session {session_id} not found."))?;
+    session.name = Some(name);
+    save_session(&session).await
+}
+
+/// Grace period a soft-deleted session sits in the trash before
+/// `purge_expired_trash` removes it for good.
+pub const TRASH_GRACE_PERIOD_MS: f64 = 30.0 * 24.0 * 60.0 * 60.0 * 1000.0; // 30 days
+
+/// Marks a session as deleted without removing it, so it can be restored
+/// until the grace period elapses.
+pub async fn soft_delete_session(session_id: &str, deleted_at_ms: f64) -> Result<()> {
+    let mut session = load_session(session_id)
+        .await?
+        .ok_or_else(|| anyhow!("[DB] SoftDelete: Session {session_id} not found."))?;
+    session.deleted_at_ms = Some(deleted_at_ms);
+    save_session(&session).await
+}
+
+/// Soft-deletes every stored session, for the "delete all" bulk action.
+pub async fn soft_delete_all_sessions(deleted_at_ms: f64) -> Result<()> {
+    let mut sessions = load_all_sessions().await?;
+    for session in &mut sessions {
+        session.deleted_at_ms = Some(deleted_at_ms);
+    }
+    save_sessions(&sessions).await
+}
+
+/// Clears the tombstone on a trashed session, moving it back to the active list.
+pub async fn restore_session(session_id: &str) -> Result<()> {
+    let mut session = load_session(session_id)
+        .await?
+        .ok_or_else(|| anyhow!("[DB] Restore: Session {session_id} not found."))?;
+    session.deleted_at_ms = None;
+    save_session(&session).await
+}
+
+/// Permanently removes any trashed session whose grace period has elapsed.
+/// Intended to be called once per app start.
+pub async fn purge_expired_trash(now_ms: f64) -> Result<()> {
+    let expired_ids: Vec<String> = load_all_sessions()
+        .await?
+        .into_iter()
+        .filter(|session| {
+            session
+                .deleted_at_ms
+                .is_some_and(|deleted_at_ms| now_ms - deleted_at_ms > TRASH_GRACE_PERIOD_MS)
+        })
+        .map(|session| session.session_id)
+        .collect();
+    delete_sessions(&expired_ids.iter().map(String::as_str).collect::<Vec<_>>()).await
+}
 
-/// Deletes a chat session from IndexedDB by its ID. (For future use)
-#[allow(dead_code)]
+/// Deletes a chat session from IndexedDB by its ID, also dropping it from
+/// every `search_index` posting list it was part of. Permanent — use
+/// `soft_delete_session` for the recoverable, user-facing delete action.
 pub async fn delete_session(session_id: &str) -> Result<()> {
+    let old_tokens = load_session(session_id)
+        .await
+        .unwrap_or(None)
+        .map(|s| session_tokens(&s))
+        .unwrap_or_default();
+
     let db = get_db().await.map_err(|e| anyhow!("[DB] Delete: DB open error: {}", e.to_string()))?;
     let tx = db
-        .transaction(&[SESSIONS_STORE_NAME], TransactionMode::ReadWrite)
+        .transaction(&[SESSIONS_STORE_NAME, SEARCH_INDEX_STORE_NAME], TransactionMode::ReadWrite)
         .map_err(|e| anyhow!("[DB] Delete: Failed to start transaction: {}", e.to_string()))?;
     let store = tx
         .object_store(SESSIONS_STORE_NAME)
@@ -225,11 +985,477 @@ pub async fn delete_session(session_id: &str) -> Result<()> {
         .await
         .map_err(|e| anyhow!("[DB] Delete: Failed to complete delete for id '{}' (async): {}", session_id, e.to_string()))?;
 
+    let index_store = tx
+        .object_store(SEARCH_INDEX_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] Delete: Failed to get search index store: {}", e.to_string()))?;
+    for token in &old_tokens {
+        remove_session_from_posting_list(&index_store, token, session_id).await?;
+    }
+
     tx.commit()
         .map_err(|e| anyhow!("[DB] Delete: Failed to initiate commit (sync): {}", e.to_string()))?
         .await
         .map_err(|e| anyhow!("[DB] Delete: Transaction commit error: {}", e.to_string()))?;
-    
+
     leptos::logging::log!("[DEBUG] [DB] Session deleted successfully: {}", session_id);
     Ok(())
 }
+
+/// Saves every session in `sessions` (and updates their `search_index`
+/// posting lists) inside a single `ReadWrite` transaction, so the whole
+/// batch commits atomically instead of leaving the store half-updated if
+/// one write fails midway. Used by the "clear all" flow
+/// (`soft_delete_all_sessions`), where per-session transactions would be
+/// both slow and non-atomic.
+pub async fn save_sessions(sessions: &[ChatSession]) -> Result<()> {
+    if sessions.is_empty() {
+        return Ok(());
+    }
+
+    let mut token_diffs = Vec::with_capacity(sessions.len());
+    for session in sessions {
+        let old_tokens = load_session(&session.session_id)
+            .await
+            .unwrap_or(None)
+            .map(|s| session_tokens(&s))
+            .unwrap_or_default();
+        token_diffs.push((session.session_id.clone(), old_tokens, session_tokens(session)));
+    }
+
+    let db = get_db().await.map_err(|e| anyhow!("[DB] SaveBatch: DB open error: {}", e.to_string()))?;
+    let tx = db
+        .transaction(&[SESSIONS_STORE_NAME, SEARCH_INDEX_STORE_NAME], TransactionMode::ReadWrite)
+        .map_err(|e| anyhow!("[DB] SaveBatch: Failed to start transaction: {}", e.to_string()))?;
+    let store = tx
+        .object_store(SESSIONS_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] SaveBatch: Failed to get object store: {}", e.to_string()))?;
+
+    for session in sessions {
+        let js_value = serde_wasm_bindgen::to_value(session).map_err(|e| {
+            anyhow!("[DB] SaveBatch: Failed to serialize session '{}': {}", session.session_id, e.to_string())
+        })?;
+        store
+            .put(&js_value, None)
+            .map_err(|e| anyhow!("[DB] SaveBatch: Failed to put session '{}' (sync): {}", session.session_id, e.to_string()))?
+            .await
+            .map_err(|e| anyhow!("[DB] SaveBatch: Failed to put session '{}' (async): {}", session.session_id, e.to_string()))?;
+    }
+
+    let index_store = tx
+        .object_store(SEARCH_INDEX_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] SaveBatch: Failed to get search index store: {}", e.to_string()))?;
+    for (session_id, old_tokens, new_tokens) in &token_diffs {
+        for token in new_tokens.difference(old_tokens) {
+            add_session_to_posting_list(&index_store, token, session_id).await?;
+        }
+        for token in old_tokens.difference(new_tokens) {
+            remove_session_from_posting_list(&index_store, token, session_id).await?;
+        }
+    }
+
+    tx.commit()
+        .map_err(|e| anyhow!("[DB] SaveBatch: Failed to initiate commit (sync): {}", e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] SaveBatch: Transaction commit error: {}", e.to_string()))?;
+
+    leptos::logging::log!("[DEBUG] [DB] Saved {} sessions in a single batch.", sessions.len());
+    Ok(())
+}
+
+/// Deletes every session id in `ids` (and drops each from every
+/// `search_index` posting list it was part of) inside a single `ReadWrite`
+/// transaction, so the whole batch commits atomically.
+pub async fn delete_sessions(ids: &[&str]) -> Result<()> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut token_sets = Vec::with_capacity(ids.len());
+    for id in ids {
+        let tokens = load_session(id)
+            .await
+            .unwrap_or(None)
+            .map(|s| session_tokens(&s))
+            .unwrap_or_default();
+        token_sets.push(tokens);
+    }
+
+    let db = get_db().await.map_err(|e| anyhow!("[DB] DeleteBatch: DB open error: {}", e.to_string()))?;
+    let tx = db
+        .transaction(&[SESSIONS_STORE_NAME, SEARCH_INDEX_STORE_NAME], TransactionMode::ReadWrite)
+        .map_err(|e| anyhow!("[DB] DeleteBatch: Failed to start transaction: {}", e.to_string()))?;
+    let store = tx
+        .object_store(SESSIONS_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] DeleteBatch: Failed to get object store: {}", e.to_string()))?;
+
+    for id in ids {
+        let key_js_value = JsValue::from_str(id);
+        store
+            .delete(idb::Query::from(key_js_value))
+            .map_err(|e| anyhow!("[DB] DeleteBatch: Failed to initiate delete for id '{}' (sync): {}", id, e.to_string()))?
+            .await
+            .map_err(|e| anyhow!("[DB] DeleteBatch: Failed to complete delete for id '{}' (async): {}", id, e.to_string()))?;
+    }
+
+    let index_store = tx
+        .object_store(SEARCH_INDEX_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] DeleteBatch: Failed to get search index store: {}", e.to_string()))?;
+    for (id, tokens) in ids.iter().zip(token_sets.iter()) {
+        for token in tokens {
+            remove_session_from_posting_list(&index_store, token, id).await?;
+        }
+    }
+
+    tx.commit()
+        .map_err(|e| anyhow!("[DB] DeleteBatch: Failed to initiate commit (sync): {}", e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] DeleteBatch: Transaction commit error: {}", e.to_string()))?;
+
+    leptos::logging::log!("[DEBUG] [DB] Deleted {} sessions in a single batch.", ids.len());
+    Ok(())
+}
+
+// --- Prompt Library CRUD ---
+
+/// Current shape of [`PromptRecord`]. Bump this and add a branch to
+/// `migrate_prompt_record` whenever the record shape changes in a way that
+/// needs real data migration, not just a new `serde(default)` field.
+const CURRENT_PROMPT_SCHEMA_VERSION: u32 = 1;
+
+/// Forward-migrates a [`PromptRecord`] loaded from IndexedDB to
+/// [`CURRENT_PROMPT_SCHEMA_VERSION`] in place. `serde(default)` already backfills
+/// newly added fields (e.g. `tags`/`description`) on deserialization, so v0 -> v1
+/// is just a version bump; later versions that need real field transformations
+/// add a branch here keyed on `record.schema_version`, same as the `MIGRATIONS`
+/// steps above do for the database itself.
+fn migrate_prompt_record(mut record: PromptRecord) -> PromptRecord {
+    if record.schema_version < CURRENT_PROMPT_SCHEMA_VERSION {
+        record.schema_version = CURRENT_PROMPT_SCHEMA_VERSION;
+    }
+    record
+}
+
+/// Saves (adds or updates) a prompt record in IndexedDB, stamping it with
+/// [`CURRENT_PROMPT_SCHEMA_VERSION`] first.
+pub async fn save_prompt(prompt: &PromptRecord) -> Result<()> {
+    let prompt = &PromptRecord { schema_version: CURRENT_PROMPT_SCHEMA_VERSION, ..prompt.clone() };
+
+    let db = get_db().await.map_err(|e| anyhow!("[DB] SavePrompt: DB open error: {}", e.to_string()))?;
+    let tx = db
+        .transaction(&[PROMPTS_STORE_NAME], TransactionMode::ReadWrite)
+        .map_err(|e| anyhow!("[DB] SavePrompt: Failed to start transaction: {}", e.to_string()))?;
+    let store = tx
+        .object_store(PROMPTS_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] SavePrompt: Failed to get object store: {}", e.to_string()))?;
+
+    let js_value = serde_wasm_bindgen::to_value(prompt)
+        .map_err(|e| anyhow!("[DB] SavePrompt: Failed to serialize prompt: {}", e.to_string()))?;
+
+    store
+        .put(&js_value, None)
+        .map_err(|e| anyhow!("[DB] SavePrompt: Failed to put prompt (sync): {}", e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] SavePrompt: Failed to put prompt (async): {}", e.to_string()))?;
+
+    tx.commit()
+        .map_err(|e| anyhow!("[DB] SavePrompt: Failed to initiate commit (sync): {}", e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] SavePrompt: Transaction commit error: {}", e.to_string()))?;
+
+    leptos::logging::log!("[DEBUG] [DB] Prompt saved successfully: {}", prompt.id);
+    Ok(())
+}
+
+/// Loads a single prompt record from IndexedDB by its ID.
+pub async fn load_prompt(prompt_id: &str) -> Result<Option<PromptRecord>> {
+    let db = get_db().await.map_err(|e| anyhow!("[DB] LoadPrompt: DB open error: {}", e.to_string()))?;
+    let tx = db
+        .transaction(&[PROMPTS_STORE_NAME], TransactionMode::ReadOnly)
+        .map_err(|e| anyhow!("[DB] LoadPrompt: Failed to start transaction: {}", e.to_string()))?;
+    let store = tx
+        .object_store(PROMPTS_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] LoadPrompt: Failed to get object store: {}", e.to_string()))?;
+
+    let key_js_value = JsValue::from_str(prompt_id);
+    let js_value_opt: Option<JsValue> = store
+        .get(idb::Query::from(key_js_value))
+        .map_err(|e| anyhow!("[DB] LoadPrompt: Failed to initiate get op for id '{}' (sync): {}", prompt_id, e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] LoadPrompt: Failed to get JsValue for id '{}' (async): {}", prompt_id, e.to_string()))?;
+
+    let prompt_opt: Option<PromptRecord> = match js_value_opt {
+        Some(js_value) => {
+            let prompt: PromptRecord = serde_wasm_bindgen::from_value(js_value)
+                .map_err(|e| anyhow!("[DB] LoadPrompt: Failed to deserialize prompt id '{}': {}", prompt_id, e.to_string()))?;
+            Some(migrate_prompt_record(prompt))
+        }
+        None => None,
+    };
+
+    tx.await
+        .map_err(|e| anyhow!("[DB] LoadPrompt: Transaction completion error: {}", e.to_string()))?;
+
+    Ok(prompt_opt)
+}
+
+/// Loads every prompt record from IndexedDB. Order is unspecified; callers
+/// that need a particular order (e.g. most-recently-updated first) should
+/// sort the result themselves.
+pub async fn load_all_prompts() -> Result<Vec<PromptRecord>> {
+    let db = get_db().await.map_err(|e| anyhow!("[DB] ListPrompts: DB open error: {}", e.to_string()))?;
+    let tx = db
+        .transaction(&[PROMPTS_STORE_NAME], TransactionMode::ReadOnly)
+        .map_err(|e| anyhow!("[DB] ListPrompts: Failed to start transaction: {}", e.to_string()))?;
+    let store = tx
+        .object_store(PROMPTS_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] ListPrompts: Failed to get object store: {}", e.to_string()))?;
+
+    let mut cursor = store
+        .open_cursor(None, None)
+        .map_err(|e| anyhow!("[DB] ListPrompts: Failed to open cursor (sync): {}", e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] ListPrompts: Failed to open cursor (async): {}", e.to_string()))?;
+
+    let mut prompts = Vec::new();
+    while let Some(c) = cursor {
+        match c.value() {
+            Ok(js_value) => match serde_wasm_bindgen::from_value::<PromptRecord>(js_value) {
+                Ok(prompt) => prompts.push(migrate_prompt_record(prompt)),
+                Err(e) => leptos::logging::log!("[WARN] [DB] ListPrompts: Failed to deserialize prompt: {:?}", e),
+            },
+            Err(e) => leptos::logging::log!("[WARN] [DB] ListPrompts: Error getting value from cursor: {:?}", e),
+        }
+        cursor = c.next(None)
+            .map_err(|e| anyhow!("[DB] ListPrompts: Failed to initiate next (sync): {}", e.to_string()))?
+            .await
+            .map_err(|e| anyhow!("[DB] ListPrompts: Error advancing cursor (async): {}", e.to_string()))?;
+    }
+
+    tx.await
+        .map_err(|e| anyhow!("[DB] ListPrompts: Transaction completion error: {}", e.to_string()))?;
+
+    leptos::logging::log!("[DEBUG] [DB] Fetched {} prompts.", prompts.len());
+    Ok(prompts)
+}
+
+/// Deletes a prompt record from IndexedDB by its ID.
+pub async fn delete_prompt(prompt_id: &str) -> Result<()> {
+    let db = get_db().await.map_err(|e| anyhow!("[DB] DeletePrompt: DB open error: {}", e.to_string()))?;
+    let tx = db
+        .transaction(&[PROMPTS_STORE_NAME], TransactionMode::ReadWrite)
+        .map_err(|e| anyhow!("[DB] DeletePrompt: Failed to start transaction: {}", e.to_string()))?;
+    let store = tx
+        .object_store(PROMPTS_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] DeletePrompt: Failed to get object store: {}", e.to_string()))?;
+
+    let key_js_value = JsValue::from_str(prompt_id);
+    store
+        .delete(idb::Query::from(key_js_value))
+        .map_err(|e| anyhow!("[DB] DeletePrompt: Failed to initiate delete for id '{}' (sync): {}", prompt_id, e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] DeletePrompt: Failed to complete delete for id '{}' (async): {}", prompt_id, e.to_string()))?;
+
+    tx.commit()
+        .map_err(|e| anyhow!("[DB] DeletePrompt: Failed to initiate commit (sync): {}", e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] DeletePrompt: Transaction commit error: {}", e.to_string()))?;
+
+    leptos::logging::log!("[DEBUG] [DB] Prompt deleted successfully: {}", prompt_id);
+    Ok(())
+}
+
+// --- Embedding Data Structure (semantic retrieval, see `crate::embeddings`) ---
+
+/// One message's embedding vector, stored alongside enough of the message
+/// itself (`session_id`, `content`) that retrieval can show and cite it
+/// without re-loading the whole session tree it came from.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmbeddingRecord {
+    #[serde(rename = "message_id")]
+    pub message_id: String,
+    pub session_id: String,
+    pub content: String,
+    pub vector: Vec<f32>,
+    pub model: String,
+    pub created_at_ms: f64,
+}
+
+/// Saves (adds or updates) an embedding record in IndexedDB.
+pub async fn save_embedding(record: &EmbeddingRecord) -> Result<()> {
+    let db = get_db().await.map_err(|e| anyhow!("[DB] SaveEmbedding: DB open error: {}", e.to_string()))?;
+    let tx = db
+        .transaction(&[EMBEDDINGS_STORE_NAME], TransactionMode::ReadWrite)
+        .map_err(|e| anyhow!("[DB] SaveEmbedding: Failed to start transaction: {}", e.to_string()))?;
+    let store = tx
+        .object_store(EMBEDDINGS_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] SaveEmbedding: Failed to get object store: {}", e.to_string()))?;
+
+    let js_value = serde_wasm_bindgen::to_value(record)
+        .map_err(|e| anyhow!("[DB] SaveEmbedding: Failed to serialize record: {}", e.to_string()))?;
+
+    store
+        .put(&js_value, None)
+        .map_err(|e| anyhow!("[DB] SaveEmbedding: Failed to put record (sync): {}", e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] SaveEmbedding: Failed to put record (async): {}", e.to_string()))?;
+
+    tx.commit()
+        .map_err(|e| anyhow!("[DB] SaveEmbedding: Failed to initiate commit (sync): {}", e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] SaveEmbedding: Transaction commit error: {}", e.to_string()))?;
+
+    leptos::logging::log!("[DEBUG] [DB] Embedding saved successfully: {}", record.message_id);
+    Ok(())
+}
+
+/// Loads every embedding record from IndexedDB, for `embeddings::top_k_similar`
+/// to scan. Order is unspecified.
+pub async fn load_all_embeddings() -> Result<Vec<EmbeddingRecord>> {
+    let db = get_db().await.map_err(|e| anyhow!("[DB] ListEmbeddings: DB open error: {}", e.to_string()))?;
+    let tx = db
+        .transaction(&[EMBEDDINGS_STORE_NAME], TransactionMode::ReadOnly)
+        .map_err(|e| anyhow!("[DB] ListEmbeddings: Failed to start transaction: {}", e.to_string()))?;
+    let store = tx
+        .object_store(EMBEDDINGS_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] ListEmbeddings: Failed to get object store: {}", e.to_string()))?;
+
+    let mut cursor = store
+        .open_cursor(None, None)
+        .map_err(|e| anyhow!("[DB] ListEmbeddings: Failed to open cursor (sync): {}", e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] ListEmbeddings: Failed to open cursor (async): {}", e.to_string()))?;
+
+    let mut records = Vec::new();
+    while let Some(c) = cursor {
+        match c.value() {
+            Ok(js_value) => match serde_wasm_bindgen::from_value::<EmbeddingRecord>(js_value) {
+                Ok(record) => records.push(record),
+                Err(e) => leptos::logging::log!("[WARN] [DB] ListEmbeddings: Failed to deserialize record: {:?}", e),
+            },
+            Err(e) => leptos::logging::log!("[WARN] [DB] ListEmbeddings: Error getting value from cursor: {:?}", e),
+        }
+        cursor = c.next(None)
+            .map_err(|e| anyhow!("[DB] ListEmbeddings: Failed to initiate next (sync): {}", e.to_string()))?
+            .await
+            .map_err(|e| anyhow!("[DB] ListEmbeddings: Error advancing cursor (async): {}", e.to_string()))?;
+    }
+
+    tx.await
+        .map_err(|e| anyhow!("[DB] ListEmbeddings: Transaction completion error: {}", e.to_string()))?;
+
+    leptos::logging::log!("[DEBUG] [DB] Fetched {} embeddings.", records.len());
+    Ok(records)
+}
+
+// --- Usage Rollup Data Structure (cost analytics, see `crate::usage`) ---
+
+/// One day's cost for one model, accumulated across however many messages
+/// landed that day so `crate::usage` can total spend without rehydrating
+/// every full session.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct UsageRollup {
+    /// `"{date}|{model_name}"`, e.g. `"2026-07-26|openai/gpt-4o"` — the
+    /// store's key path, derived rather than stored as separate fields so
+    /// every existing store's single-field `KeyPath` convention still holds.
+    pub rollup_key: String,
+    /// `YYYY-MM-DD`, the UTC date (from `Date::toISOString`) at the time usage was recorded.
+    pub date: String,
+    pub model_name: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub prompt_cost_usd: f64,
+    pub completion_cost_usd: f64,
+}
+
+impl UsageRollup {
+    pub fn rollup_key(date: &str, model_name: &str) -> String {
+        format!("{date}|{model_name}")
+    }
+}
+
+/// Adds `usage`/`cost` to today's rollup for `model_name`, creating it if
+/// this is the first message recorded for that date/model pair.
+pub async fn record_usage_rollup(date: &str, model_name: &str, usage: &crate::llm::Usage, cost: &crate::chat::types::MessageCost) -> Result<()> {
+    let db = get_db().await.map_err(|e| anyhow!("[DB] RecordUsageRollup: DB open error: {}", e.to_string()))?;
+    let tx = db
+        .transaction(&[USAGE_ROLLUPS_STORE_NAME], TransactionMode::ReadWrite)
+        .map_err(|e| anyhow!("[DB] RecordUsageRollup: Failed to start transaction: {}", e.to_string()))?;
+    let store = tx
+        .object_store(USAGE_ROLLUPS_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] RecordUsageRollup: Failed to get object store: {}", e.to_string()))?;
+
+    let rollup_key = UsageRollup::rollup_key(date, model_name);
+    let key_js_value = JsValue::from_str(&rollup_key);
+
+    let existing: Option<JsValue> = store
+        .get(idb::Query::from(key_js_value))
+        .map_err(|e| anyhow!("[DB] RecordUsageRollup: Failed to get existing record (sync): {}", e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] RecordUsageRollup: Failed to get existing record (async): {}", e.to_string()))?;
+
+    let mut rollup = match existing {
+        Some(js_value) => serde_wasm_bindgen::from_value::<UsageRollup>(js_value)
+            .unwrap_or_else(|_| UsageRollup { rollup_key: rollup_key.clone(), date: date.to_string(), model_name: model_name.to_string(), ..Default::default() }),
+        None => UsageRollup { rollup_key: rollup_key.clone(), date: date.to_string(), model_name: model_name.to_string(), ..Default::default() },
+    };
+    rollup.prompt_tokens += usage.prompt_tokens as u64;
+    rollup.completion_tokens += usage.completion_tokens as u64;
+    rollup.prompt_cost_usd += cost.prompt;
+    rollup.completion_cost_usd += cost.completion;
+
+    let js_value = serde_wasm_bindgen::to_value(&rollup)
+        .map_err(|e| anyhow!("[DB] RecordUsageRollup: Failed to serialize record: {}", e.to_string()))?;
+
+    store
+        .put(&js_value, None)
+        .map_err(|e| anyhow!("[DB] RecordUsageRollup: Failed to put record (sync): {}", e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] RecordUsageRollup: Failed to put record (async): {}", e.to_string()))?;
+
+    tx.commit()
+        .map_err(|e| anyhow!("[DB] RecordUsageRollup: Failed to initiate commit (sync): {}", e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] RecordUsageRollup: Transaction commit error: {}", e.to_string()))?;
+
+    leptos::logging::log!("[DEBUG] [DB] Usage rollup updated: {}", rollup_key);
+    Ok(())
+}
+
+/// Loads every usage rollup from IndexedDB, for `crate::usage` to total and
+/// group. Order is unspecified.
+pub async fn load_all_usage_rollups() -> Result<Vec<UsageRollup>> {
+    let db = get_db().await.map_err(|e| anyhow!("[DB] ListUsageRollups: DB open error: {}", e.to_string()))?;
+    let tx = db
+        .transaction(&[USAGE_ROLLUPS_STORE_NAME], TransactionMode::ReadOnly)
+        .map_err(|e| anyhow!("[DB] ListUsageRollups: Failed to start transaction: {}", e.to_string()))?;
+    let store = tx
+        .object_store(USAGE_ROLLUPS_STORE_NAME)
+        .map_err(|e| anyhow!("[DB] ListUsageRollups: Failed to get object store: {}", e.to_string()))?;
+
+    let mut cursor = store
+        .open_cursor(None, None)
+        .map_err(|e| anyhow!("[DB] ListUsageRollups: Failed to open cursor (sync): {}", e.to_string()))?
+        .await
+        .map_err(|e| anyhow!("[DB] ListUsageRollups: Failed to open cursor (async): {}", e.to_string()))?;
+
+    let mut records = Vec::new();
+    while let Some(c) = cursor {
+        match c.value() {
+            Ok(js_value) => match serde_wasm_bindgen::from_value::<UsageRollup>(js_value) {
+                Ok(record) => records.push(record),
+                Err(e) => leptos::logging::log!("[WARN] [DB] ListUsageRollups: Failed to deserialize record: {:?}", e),
+            },
+            Err(e) => leptos::logging::log!("[WARN] [DB] ListUsageRollups: Error getting value from cursor: {:?}", e),
+        }
+        cursor = c.next(None)
+            .map_err(|e| anyhow!("[DB] ListUsageRollups: Failed to initiate next (sync): {}", e.to_string()))?
+            .await
+            .map_err(|e| anyhow!("[DB] ListUsageRollups: Error advancing cursor (async): {}", e.to_string()))?;
+    }
+
+    tx.await
+        .map_err(|e| anyhow!("[DB] ListUsageRollups: Transaction completion error: {}", e.to_string()))?;
+
+    leptos::logging::log!("[DEBUG] [DB] Fetched {} usage rollups.", records.len());
+    Ok(records)
+}
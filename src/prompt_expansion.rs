@@ -0,0 +1,83 @@
+use anyhow::{anyhow, Result};
+use futures::future::try_join_all;
+use std::collections::HashMap;
+use web_sys::js_sys::Date;
+
+/// Values available to `{{name}}` placeholders when expanding a prompt, e.g.
+/// the currently selected chat text under `"selection"`.
+#[derive(Debug, Clone, Default)]
+pub struct ExpansionContext {
+    pub vars: HashMap<String, String>,
+}
+
+/// Resolves a single `/command args` line to its expansion text. Unknown
+/// commands are an error rather than being left untouched, so a typo
+/// doesn't silently ship as literal text to the model.
+async fn resolve_command(name: &str, args: &str) -> Result<String> {
+    match name {
+        "date" => Ok(Date::new_0().to_iso_string().as_string().unwrap_or_default()),
+        "fetch" => fetch_url_text(args.trim()).await,
+        other => Err(anyhow!("[PromptExpansion] Unknown command: /{other}")),
+    }
+}
+
+async fn fetch_url_text(url: &str) -> Result<String> {
+    if url.is_empty() {
+        return Err(anyhow!("[PromptExpansion] /fetch requires a URL argument"));
+    }
+    let response = reqwest::get(url)
+        .await
+        .map_err(|e| anyhow!("[PromptExpansion] /fetch {url}: request failed: {e}"))?;
+    response
+        .text()
+        .await
+        .map_err(|e| anyhow!("[PromptExpansion] /fetch {url}: failed to read body: {e}"))
+}
+
+/// Scans `body` for `/command` lines (first non-whitespace character of the
+/// line is `/`) and `{{var}}` placeholders, resolves the commands
+/// concurrently and the placeholders from `ctx.vars`, then substitutes the
+/// results. Returns an error instead of a half-expanded prompt if any
+/// command fails or a placeholder has no matching value.
+pub async fn expand_prompt(body: &str, ctx: &ExpansionContext) -> Result<String> {
+    let mut command_line_indices = Vec::new();
+    let mut command_futures = Vec::new();
+    for (line_index, line) in body.lines().enumerate() {
+        if let Some(rest) = line.trim_start().strip_prefix('/') {
+            let mut parts = rest.splitn(2, char::is_whitespace);
+            let name = parts.next().unwrap_or("").to_string();
+            let args = parts.next().unwrap_or("").to_string();
+            command_line_indices.push(line_index);
+            command_futures.push(async move { resolve_command(&name, &args).await });
+        }
+    }
+    let command_results = try_join_all(command_futures).await?;
+
+    let mut out_lines: Vec<String> = body.lines().map(str::to_string).collect();
+    for (line_index, resolved) in command_line_indices.into_iter().zip(command_results) {
+        out_lines[line_index] = resolved;
+    }
+    let mut expanded = out_lines.join("\n");
+
+    let mut placeholder_names = Vec::new();
+    let mut remaining = expanded.as_str();
+    while let Some(start) = remaining.find("{{") {
+        match remaining[start..].find("}}") {
+            Some(end) => {
+                let name = remaining[start + 2..start + end].trim().to_string();
+                placeholder_names.push(name);
+                remaining = &remaining[start + end + 2..];
+            }
+            None => break,
+        }
+    }
+    for name in placeholder_names {
+        let value = ctx
+            .vars
+            .get(&name)
+            .ok_or_else(|| anyhow!("[PromptExpansion] Unresolved placeholder: {{{{{name}}}}}"))?;
+        expanded = expanded.replace(&format!("{{{{{name}}}}}"), value);
+    }
+
+    Ok(expanded)
+}
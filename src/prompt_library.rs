@@ -0,0 +1,154 @@
+use crate::persistence::PromptRecord;
+use serde::{Deserialize, Serialize};
+
+/// Scores `record` against `query` by rewarding a contiguous substring match
+/// in the title highest, then a substring match in the body, then falling
+/// back to an in-order subsequence match of the title. Returns `None` if
+/// `query` doesn't match at all.
+fn fuzzy_score(query: &str, record: &PromptRecord) -> Option<i64> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let query_lower = query.to_lowercase();
+    let title_lower = record.title.to_lowercase();
+    let body_lower = record.body.to_lowercase();
+
+    if let Some(pos) = title_lower.find(&query_lower) {
+        return Some(2_000_000 - pos as i64);
+    }
+    if let Some(pos) = body_lower.find(&query_lower) {
+        return Some(1_000_000 - pos as i64);
+    }
+
+    let mut chars = title_lower.chars();
+    let mut matched = 0i64;
+    for qc in query_lower.chars() {
+        if chars.find(|tc| *tc == qc).is_some() {
+            matched += 1;
+        } else {
+            return None;
+        }
+    }
+    Some(matched)
+}
+
+/// Searches `prompts` by title/body substring (falling back to a subsequence
+/// match on the title), optionally narrowed to records carrying `tag`.
+/// Results are sorted best match first.
+pub fn search_prompts<'a>(
+    prompts: &'a [PromptRecord],
+    query: &str,
+    tag: Option<&str>,
+) -> Vec<&'a PromptRecord> {
+    let mut scored: Vec<(i64, &PromptRecord)> = prompts
+        .iter()
+        .filter(|p| tag.map_or(true, |tag| p.tags.iter().any(|t| t == tag)))
+        .filter_map(|p| fuzzy_score(query, p).map(|score| (score, p)))
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0));
+    scored.into_iter().map(|(_, p)| p).collect()
+}
+
+/// JSON-serializable snapshot of the prompt library, for export/import.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptLibraryExport {
+    pub prompts: Vec<PromptRecord>,
+}
+
+/// Serializes the given prompts to a pretty-printed JSON export document.
+pub fn export_json(prompts: &[PromptRecord]) -> anyhow::Result<String> {
+    let export = PromptLibraryExport {
+        prompts: prompts.to_vec(),
+    };
+    serde_json::to_string_pretty(&export)
+        .map_err(|e| anyhow::anyhow!("[PromptLibrary] Export: Failed to serialize: {}", e))
+}
+
+/// Parses a previously exported JSON document back into prompt records.
+pub fn import_json(json: &str) -> anyhow::Result<Vec<PromptRecord>> {
+    let export: PromptLibraryExport = serde_json::from_str(json)
+        .map_err(|e| anyhow::anyhow!("[PromptLibrary] Import: Failed to parse: {}", e))?;
+    Ok(export.prompts)
+}
+
+/// A prompt's fields as parsed from a Markdown+frontmatter document (see
+/// `parse_markdown_prompt`). `id`/timestamps aren't part of the document
+/// format, so the caller fills those in when constructing a `PromptRecord`,
+/// same as every other prompt-creation site.
+pub struct ParsedPrompt {
+    pub title: String,
+    pub tags: Vec<String>,
+    pub description: Option<String>,
+    pub body: String,
+}
+
+/// Parses a Markdown document for single-prompt import/export: a leading
+/// `---\n` ... `\n---\n` block of simple `key: value` lines (`name:`,
+/// `description:`, and `tags:` either as `[a, b]` or a `- a` / `- b` list),
+/// followed by the prompt body. If there's no leading frontmatter fence,
+/// the whole document is the body and `fallback_name` (typically the
+/// imported filename) becomes the title.
+pub fn parse_markdown_prompt(content: &str, fallback_name: &str) -> ParsedPrompt {
+    let whole_document_as_body = || ParsedPrompt {
+        title: fallback_name.to_string(),
+        tags: Vec::new(),
+        description: None,
+        body: content.to_string(),
+    };
+
+    let Some(after_open_fence) = content.strip_prefix("---\n") else {
+        return whole_document_as_body();
+    };
+    let Some(fence_end) = after_open_fence.find("\n---\n") else {
+        return whole_document_as_body();
+    };
+    let frontmatter = &after_open_fence[..fence_end];
+    let body = after_open_fence[fence_end + "\n---\n".len()..].to_string();
+
+    let mut title = None;
+    let mut tags = Vec::new();
+    let mut description = None;
+    let unquote = |value: &str| value.trim().trim_matches('"').to_string();
+    for line in frontmatter.lines() {
+        let line = line.trim();
+        if let Some(value) = line.strip_prefix("name:") {
+            title = Some(unquote(value));
+        } else if let Some(value) = line.strip_prefix("description:") {
+            description = Some(unquote(value));
+        } else if let Some(value) = line.strip_prefix("tags:") {
+            let value = value.trim();
+            if let Some(inline) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                tags = inline
+                    .split(',')
+                    .map(|t| unquote(t))
+                    .filter(|t| !t.is_empty())
+                    .collect();
+            }
+        } else if let Some(item) = line.strip_prefix("- ") {
+            tags.push(unquote(item));
+        }
+    }
+
+    ParsedPrompt {
+        title: title.filter(|t| !t.is_empty()).unwrap_or_else(|| fallback_name.to_string()),
+        tags,
+        description,
+        body,
+    }
+}
+
+/// Serializes `prompt` as a Markdown document with YAML frontmatter — the
+/// inverse of `parse_markdown_prompt`.
+pub fn to_markdown_prompt(prompt: &PromptRecord) -> String {
+    let mut frontmatter = format!("name: {}\n", prompt.title);
+    if let Some(description) = &prompt.description {
+        frontmatter.push_str(&format!("description: {description}\n"));
+    }
+    if !prompt.tags.is_empty() {
+        frontmatter.push_str("tags:\n");
+        for tag in &prompt.tags {
+            frontmatter.push_str(&format!("  - {tag}\n"));
+        }
+    }
+    format!("---\n{frontmatter}---\n{}", prompt.body)
+}
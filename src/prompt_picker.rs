@@ -0,0 +1,205 @@
+use leptos::html;
+use leptos::prelude::*;
+
+use crate::chat::types::SystemPrompt;
+
+/// True if `idx` starts a new "word" in `text`: the very first character, or
+/// one immediately after a space/`-`/`_`, or a lowercase-to-uppercase
+/// (camelCase) transition.
+fn is_word_boundary(chars: &[char], idx: usize) -> bool {
+    if idx == 0 {
+        return true;
+    }
+    let prev = chars[idx - 1];
+    let cur = chars[idx];
+    prev == ' ' || prev == '-' || prev == '_' || (prev.is_lowercase() && cur.is_uppercase())
+}
+
+/// Scores `text` against `query` as a left-to-right, in-order subsequence
+/// match: a base point per matched character, a bonus for landing on a word
+/// boundary or immediately continuing the previous match, and a penalty
+/// proportional to the gap skipped to reach the next match. Returns the
+/// total score and the matched character indices into `text` (for
+/// highlighting), or `None` if `query` isn't a subsequence of `text` at all.
+fn fuzzy_match(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    let text_chars: Vec<char> = text.chars().collect();
+    let text_lower: Vec<char> = text_chars.iter().map(|c| c.to_ascii_lowercase()).collect();
+    let query_lower: Vec<char> = query.to_lowercase().chars().collect();
+
+    let mut score = 0i64;
+    let mut matched_indices = Vec::with_capacity(query_lower.len());
+    let mut cursor = 0usize;
+    let mut last_match: Option<usize> = None;
+
+    for &qc in &query_lower {
+        let offset = text_lower[cursor..].iter().position(|&tc| tc == qc)?;
+        let idx = cursor + offset;
+
+        score += 10;
+        if is_word_boundary(&text_chars, idx) {
+            score += 8;
+        }
+        match last_match {
+            Some(prev) if idx == prev + 1 => score += 5,
+            Some(prev) => score -= (idx - prev) as i64,
+            None => {}
+        }
+
+        matched_indices.push(idx);
+        last_match = Some(idx);
+        cursor = idx + 1;
+    }
+
+    Some((score, matched_indices))
+}
+
+/// Renders `text` with the characters at `matched_indices` highlighted.
+fn render_highlighted(text: &str, matched_indices: &[usize]) -> impl IntoView {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let is_match = matched_indices.contains(&i);
+        let start = i;
+        while i < chars.len() && matched_indices.contains(&i) == is_match {
+            i += 1;
+        }
+        let segment: String = chars[start..i].iter().collect();
+        if is_match {
+            spans.push(
+                view! {
+                    <mark style="background:var(--highlight-color, #2a4); color:inherit;">
+                        {segment}
+                    </mark>
+                }
+                    .into_any(),
+            );
+        } else {
+            spans.push(view! { <span>{segment}</span> }.into_any());
+        }
+    }
+    spans.into_iter().collect_view()
+}
+
+/// A keyboard-driven command-palette-style picker for jumping straight to a
+/// system prompt by fuzzy-matching its name, instead of scanning the
+/// `SystemPromptBar` button row. Opened from `Header`.
+#[component]
+pub fn PromptPicker(
+    #[prop(into)] open: RwSignal<bool>,
+    #[prop(into)] system_prompts: Signal<Vec<SystemPrompt>>,
+    #[prop(into)] set_selected_prompt_name: WriteSignal<Option<String>>,
+) -> impl IntoView {
+    let query = RwSignal::new(String::new());
+    let highlighted_index = RwSignal::new(0usize);
+    let input_ref: NodeRef<html::Input> = NodeRef::new();
+
+    Effect::new(move |_| {
+        if open.get() {
+            query.set(String::new());
+            highlighted_index.set(0);
+            if let Some(input) = input_ref.get() {
+                let _ = input.focus();
+            }
+        }
+    });
+
+    let matches = Memo::new(move |_| {
+        let query = query.get();
+        let mut scored: Vec<(i64, Vec<usize>, String)> = system_prompts
+            .get()
+            .into_iter()
+            .filter_map(|prompt| {
+                let (score, indices) = fuzzy_match(&query, &prompt.name)?;
+                Some((score, indices, prompt.name))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0));
+        scored
+    });
+
+    let select = move |name: String| {
+        set_selected_prompt_name.set(Some(name));
+        open.set(false);
+    };
+
+    view! {
+        <Show when=move || open.get()>
+            <div
+                class="prompt-picker-backdrop"
+                style="position:fixed; inset:0; background:rgba(0,0,0,0.4); z-index:100; display:flex; align-items:flex-start; justify-content:center;"
+                on:click=move |_| open.set(false)
+            >
+                <div
+                    class="prompt-picker"
+                    style="margin-top:10vh; width:min(560px, 90vw); background:var(--background-color); border:1px solid var(--border-color); border-radius:8px; overflow:hidden;"
+                    on:click=move |ev| ev.stop_propagation()
+                >
+                    <input
+                        type="text"
+                        node_ref=input_ref
+                        placeholder="Switch system prompt..."
+                        style="width:100%; padding:8px; border:none; border-bottom:1px solid var(--border-color);"
+                        prop:value=query
+                        on:input:target=move |ev| {
+                            query.set(ev.target().value());
+                            highlighted_index.set(0);
+                        }
+                        on:keydown=move |ev| {
+                            match ev.key().as_str() {
+                                "Escape" => open.set(false),
+                                "ArrowDown" => {
+                                    ev.prevent_default();
+                                    let len = matches.get_untracked().len();
+                                    if len > 0 {
+                                        highlighted_index.update(|i| *i = (*i + 1) % len);
+                                    }
+                                }
+                                "ArrowUp" => {
+                                    ev.prevent_default();
+                                    let len = matches.get_untracked().len();
+                                    if len > 0 {
+                                        highlighted_index.update(|i| *i = (*i + len - 1) % len);
+                                    }
+                                }
+                                "Enter" => {
+                                    let matches = matches.get_untracked();
+                                    if let Some((_, _, name)) = matches.get(highlighted_index.get_untracked()) {
+                                        select(name.clone());
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                    />
+                    <ul style="max-height:50vh; overflow-y:auto; margin:0; padding:0; list-style:none;">
+                        {move || {
+                            matches
+                                .get()
+                                .into_iter()
+                                .enumerate()
+                                .map(|(index, (_, indices, name))| {
+                                    let is_highlighted = Memo::new(move |_| highlighted_index.get() == index);
+                                    let name_for_click = name.clone();
+                                    view! {
+                                        <li
+                                            style="padding:8px; cursor:pointer; border-bottom:1px solid var(--border-color);"
+                                            class:prompt-picker-item-highlighted=is_highlighted
+                                            on:click=move |_| select(name_for_click.clone())
+                                        >
+                                            {render_highlighted(&name, &indices)}
+                                        </li>
+                                    }
+                                })
+                                .collect_view()
+                        }}
+                    </ul>
+                </div>
+            </div>
+        </Show>
+    }
+}
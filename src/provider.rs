@@ -0,0 +1,461 @@
+//! Pluggable LLM backend. `llm::request_message_content_streamed` and
+//! `llm::list_available_models` used to construct an `OpenRouterClient`
+//! inline, locking the whole crate to one backend. They now dispatch through
+//! [`Provider`] instead, so OpenAI-compatible or local backends are just
+//! another impl behind the same `Message`/`Model`/`DisplayModelInfo`
+//! vocabulary `llm` already defines.
+
+use crate::llm::{DisplayModelInfo, Message, Model, StreamedMessage};
+use async_trait::async_trait;
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use std::pin::Pin;
+
+/// Selects which backend a [`Model`] talks to. Defaults to `OpenRouter`, the
+/// only backend this crate originally supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderKind {
+    #[default]
+    OpenRouter,
+    OpenAi,
+    Ollama,
+}
+
+impl ProviderKind {
+    /// Every variant, in the order the `Settings` provider selector lists them.
+    pub const ALL: [ProviderKind; 3] = [ProviderKind::OpenRouter, ProviderKind::OpenAi, ProviderKind::Ollama];
+
+    /// Human-readable label for the `Settings` provider selector.
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProviderKind::OpenRouter => "OpenRouter",
+            ProviderKind::OpenAi => "OpenAI",
+            ProviderKind::Ollama => "Ollama (local)",
+        }
+    }
+}
+
+/// Credentials a [`Provider`] needs. Every adapter accepts this same shape
+/// even though e.g. `Ollama` (local, unauthenticated) ignores `api_key`.
+#[derive(Debug, Clone, Default)]
+pub struct ProviderCreds {
+    pub api_key: String,
+}
+
+/// A streamed chat completion, boxed so every adapter can return its own
+/// concrete stream type behind one signature.
+pub type ChatStream = Pin<Box<dyn Stream<Item = anyhow::Result<StreamedMessage>>>>;
+
+/// One backend's base URL, auth scheme, and request/response mapping, behind
+/// the shared `Message`/`Model`/`DisplayModelInfo` vocabulary. `?Send`
+/// because callers run this in a single-threaded wasm event loop.
+#[async_trait(?Send)]
+pub trait Provider {
+    async fn chat_stream(
+        &self,
+        messages: Vec<Message>,
+        model_config: Model,
+        creds: ProviderCreds,
+    ) -> anyhow::Result<ChatStream>;
+
+    async fn list_models(&self, creds: ProviderCreds) -> anyhow::Result<Vec<DisplayModelInfo>>;
+
+    /// Requests an embedding vector for each of `texts`, in the same order.
+    /// Defaults to unsupported so adapters without an embeddings API (e.g.
+    /// `Ollama`) don't need their own override.
+    async fn embed(
+        &self,
+        _texts: Vec<String>,
+        _model: &str,
+        _creds: ProviderCreds,
+    ) -> anyhow::Result<Vec<Vec<f32>>> {
+        Err(anyhow::anyhow!("This provider does not support embeddings."))
+    }
+}
+
+/// Resolves the adapter for `kind`.
+pub fn provider_for(kind: ProviderKind) -> Box<dyn Provider> {
+    match kind {
+        ProviderKind::OpenRouter => Box::new(openrouter::OpenRouterProvider),
+        ProviderKind::OpenAi => Box::new(openai_compatible::OpenAiProvider),
+        ProviderKind::Ollama => Box::new(ollama::OllamaProvider),
+    }
+}
+
+mod openrouter {
+    use super::{ChatStream, Provider, ProviderCreds};
+    use crate::llm::{DisplayModelInfo, Message, Model};
+    use async_trait::async_trait;
+
+    /// The original backend: OpenRouter's hosted, multi-model API.
+    pub struct OpenRouterProvider;
+
+    #[async_trait(?Send)]
+    impl Provider for OpenRouterProvider {
+        async fn chat_stream(
+            &self,
+            messages: Vec<Message>,
+            model_config: Model,
+            creds: ProviderCreds,
+        ) -> anyhow::Result<ChatStream> {
+            let stream = crate::llm::openrouter_chat_stream(messages, model_config, creds.api_key).await?;
+            Ok(Box::pin(stream))
+        }
+
+        async fn list_models(&self, creds: ProviderCreds) -> anyhow::Result<Vec<DisplayModelInfo>> {
+            crate::llm::openrouter_list_models(creds.api_key).await
+        }
+
+        async fn embed(
+            &self,
+            texts: Vec<String>,
+            model: &str,
+            creds: ProviderCreds,
+        ) -> anyhow::Result<Vec<Vec<f32>>> {
+            crate::llm::openrouter_embed(texts, model, creds.api_key).await
+        }
+    }
+}
+
+/// OpenAI's official API over the `/v1/chat/completions` + SSE wire format.
+/// The `*_at` helpers take the base URL as a parameter, but `OpenAiProvider`
+/// is the only adapter built on them and always targets
+/// `api.openai.com/v1`; no other (e.g. self-hosted) base URL is selectable.
+mod openai_compatible {
+    use super::{ChatStream, Provider, ProviderCreds};
+    use crate::llm::{DisplayModelInfo, Message, StreamedMessage, Usage, Model};
+    use anyhow::{anyhow, Result};
+    use async_stream::stream;
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    /// Parses one `data: {...}` line of an OpenAI-style SSE chat stream into
+    /// a `StreamedMessage`, or `None` for the terminating `data: [DONE]` line
+    /// and any line that isn't a `data:` event at all.
+    fn parse_sse_line(line: &str) -> Option<Result<StreamedMessage>> {
+        let payload = line.strip_prefix("data:")?.trim();
+        if payload == "[DONE]" {
+            return None;
+        }
+
+        #[derive(Deserialize)]
+        struct Delta {
+            content: Option<String>,
+        }
+        #[derive(Deserialize)]
+        struct Choice {
+            delta: Delta,
+        }
+        #[derive(Deserialize)]
+        struct ChunkUsage {
+            prompt_tokens: u32,
+            completion_tokens: u32,
+        }
+        #[derive(Deserialize)]
+        struct Chunk {
+            #[serde(default)]
+            choices: Vec<Choice>,
+            usage: Option<ChunkUsage>,
+        }
+
+        let chunk: Chunk = match serde_json::from_str(payload) {
+            Ok(chunk) => chunk,
+            Err(e) => return Some(Err(anyhow!("Failed to parse SSE chunk: {e}"))),
+        };
+
+        if let Some(usage) = chunk.usage {
+            return Some(Ok(StreamedMessage::Usage(Usage {
+                prompt_tokens: usage.prompt_tokens,
+                completion_tokens: usage.completion_tokens,
+            })));
+        }
+        let content = chunk.choices.into_iter().next()?.delta.content?;
+        Some(Ok(StreamedMessage::Content(content)))
+    }
+
+    async fn chat_stream_at(
+        base_url: &str,
+        messages: Vec<Message>,
+        model_config: Model,
+        creds: ProviderCreds,
+    ) -> Result<ChatStream> {
+        if creds.api_key.is_empty() {
+            return Err(anyhow!("API key is missing."));
+        }
+
+        let body = json!({
+            "model": model_config.model,
+            "messages": messages.into_iter().map(|m| json!({"role": m.role, "content": m.content})).collect::<Vec<_>>(),
+            "stream": true,
+            "seed": model_config.seed,
+            "temperature": model_config.temperature,
+        });
+
+        let response = reqwest::Client::new()
+            .post(format!("{base_url}/chat/completions"))
+            .bearer_auth(creds.api_key)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Request failed: {e}"))?
+            .error_for_status()
+            .map_err(|e| anyhow!("Request failed: {e}"))?;
+
+        let mut bytes_stream = response.bytes_stream();
+        let output_stream = stream! {
+            let mut buffer = String::new();
+            while let Some(chunk) = bytes_stream.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        yield Err(anyhow!("Error reading response stream: {e}"));
+                        break;
+                    }
+                };
+                buffer.push_str(&String::from_utf8_lossy(&chunk));
+                while let Some(newline_pos) = buffer.find('\n') {
+                    let line = buffer[..newline_pos].trim().to_string();
+                    buffer.drain(..=newline_pos);
+                    if let Some(result) = parse_sse_line(&line) {
+                        yield result;
+                    }
+                }
+            }
+        };
+
+        Ok(Box::pin(output_stream))
+    }
+
+    async fn list_models_at(base_url: &str, creds: ProviderCreds) -> Result<Vec<DisplayModelInfo>> {
+        if creds.api_key.is_empty() {
+            return Err(anyhow!("API key is missing."));
+        }
+
+        #[derive(Deserialize)]
+        struct ModelEntry {
+            id: String,
+        }
+        #[derive(Deserialize)]
+        struct ModelList {
+            data: Vec<ModelEntry>,
+        }
+
+        let list: ModelList = reqwest::Client::new()
+            .get(format!("{base_url}/models"))
+            .bearer_auth(creds.api_key)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Request failed: {e}"))?
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse model list: {e}"))?;
+
+        Ok(list
+            .data
+            .into_iter()
+            .map(|m| DisplayModelInfo {
+                name: m.id.clone(),
+                id: m.id,
+                prompt_cost_usd_pm: None,
+                completion_cost_usd_pm: None,
+                context_length: None,
+            })
+            .collect())
+    }
+
+    async fn embed_at(
+        base_url: &str,
+        texts: Vec<String>,
+        model: &str,
+        creds: ProviderCreds,
+    ) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        if creds.api_key.is_empty() {
+            return Err(anyhow!("API key is missing."));
+        }
+
+        #[derive(Deserialize)]
+        struct EmbeddingDatum {
+            embedding: Vec<f32>,
+        }
+        #[derive(Deserialize)]
+        struct EmbeddingResponse {
+            data: Vec<EmbeddingDatum>,
+        }
+
+        let response = reqwest::Client::new()
+            .post(format!("{base_url}/embeddings"))
+            .bearer_auth(creds.api_key)
+            .json(&json!({"model": model, "input": texts}))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Embedding request failed: {e}"))?
+            .error_for_status()
+            .map_err(|e| anyhow!("Embedding request failed: {e}"))?;
+
+        let parsed: EmbeddingResponse = response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse embedding response: {e}"))?;
+        Ok(parsed.data.into_iter().map(|d| d.embedding).collect())
+    }
+
+    /// OpenAI's own hosted API, `api.openai.com/v1`.
+    pub struct OpenAiProvider;
+
+    #[async_trait(?Send)]
+    impl Provider for OpenAiProvider {
+        async fn chat_stream(
+            &self,
+            messages: Vec<Message>,
+            model_config: Model,
+            creds: ProviderCreds,
+        ) -> anyhow::Result<ChatStream> {
+            chat_stream_at("https://api.openai.com/v1", messages, model_config, creds).await
+        }
+
+        async fn list_models(&self, creds: ProviderCreds) -> anyhow::Result<Vec<DisplayModelInfo>> {
+            list_models_at("https://api.openai.com/v1", creds).await
+        }
+
+        async fn embed(
+            &self,
+            texts: Vec<String>,
+            model: &str,
+            creds: ProviderCreds,
+        ) -> anyhow::Result<Vec<Vec<f32>>> {
+            embed_at("https://api.openai.com/v1", texts, model, creds).await
+        }
+    }
+}
+
+/// A local Ollama server (`http://localhost:11434`). Unauthenticated, and
+/// its wire format is newline-delimited JSON objects rather than SSE.
+mod ollama {
+    use super::{ChatStream, Provider, ProviderCreds};
+    use crate::llm::{DisplayModelInfo, Message, Model, StreamedMessage};
+    use anyhow::{anyhow, Result};
+    use async_stream::stream;
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    const BASE_URL: &str = "http://localhost:11434";
+
+    pub struct OllamaProvider;
+
+    #[async_trait(?Send)]
+    impl Provider for OllamaProvider {
+        async fn chat_stream(
+            &self,
+            messages: Vec<Message>,
+            model_config: Model,
+            _creds: ProviderCreds,
+        ) -> anyhow::Result<ChatStream> {
+            let body = json!({
+                "model": model_config.model,
+                "messages": messages.into_iter().map(|m| json!({"role": m.role, "content": m.content})).collect::<Vec<_>>(),
+                "stream": true,
+            });
+
+            let response = reqwest::Client::new()
+                .post(format!("{BASE_URL}/api/chat"))
+                .json(&body)
+                .send()
+                .await
+                .map_err(|e| anyhow!("Request to local Ollama server failed: {e}"))?
+                .error_for_status()
+                .map_err(|e| anyhow!("Request to local Ollama server failed: {e}"))?;
+
+            #[derive(Deserialize)]
+            struct ChatMessage {
+                content: String,
+            }
+            #[derive(Deserialize)]
+            struct ChatChunk {
+                message: Option<ChatMessage>,
+                done: bool,
+                prompt_eval_count: Option<u32>,
+                eval_count: Option<u32>,
+            }
+
+            let mut bytes_stream = response.bytes_stream();
+            let output_stream = stream! {
+                let mut buffer = String::new();
+                while let Some(chunk) = bytes_stream.next().await {
+                    let chunk = match chunk {
+                        Ok(chunk) => chunk,
+                        Err(e) => {
+                            yield Err(anyhow!("Error reading response stream: {e}"));
+                            break;
+                        }
+                    };
+                    buffer.push_str(&String::from_utf8_lossy(&chunk));
+                    while let Some(newline_pos) = buffer.find('\n') {
+                        let line = buffer[..newline_pos].trim().to_string();
+                        buffer.drain(..=newline_pos);
+                        if line.is_empty() {
+                            continue;
+                        }
+                        match serde_json::from_str::<ChatChunk>(&line) {
+                            Ok(parsed) => {
+                                if let Some(message) = parsed.message {
+                                    if !message.content.is_empty() {
+                                        yield Ok(StreamedMessage::Content(message.content));
+                                    }
+                                }
+                                if parsed.done {
+                                    yield Ok(StreamedMessage::Usage(crate::llm::Usage {
+                                        prompt_tokens: parsed.prompt_eval_count.unwrap_or(0),
+                                        completion_tokens: parsed.eval_count.unwrap_or(0),
+                                    }));
+                                }
+                            }
+                            Err(e) => yield Err(anyhow!("Failed to parse Ollama chunk: {e}")),
+                        }
+                    }
+                }
+            };
+
+            Ok(Box::pin(output_stream))
+        }
+
+        async fn list_models(&self, _creds: ProviderCreds) -> anyhow::Result<Vec<DisplayModelInfo>> {
+            #[derive(Deserialize)]
+            struct OllamaModel {
+                name: String,
+            }
+            #[derive(Deserialize)]
+            struct OllamaTags {
+                models: Vec<OllamaModel>,
+            }
+
+            let tags: OllamaTags = reqwest::Client::new()
+                .get(format!("{BASE_URL}/api/tags"))
+                .send()
+                .await
+                .map_err(|e| anyhow!("Request to local Ollama server failed: {e}"))?
+                .json()
+                .await
+                .map_err(|e| anyhow!("Failed to parse model list: {e}"))?;
+
+            Ok(tags
+                .models
+                .into_iter()
+                .map(|m| DisplayModelInfo {
+                    id: m.name.clone(),
+                    name: m.name,
+                    prompt_cost_usd_pm: None,
+                    completion_cost_usd_pm: None,
+                    context_length: None,
+                })
+                .collect())
+        }
+    }
+}
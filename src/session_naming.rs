@@ -0,0 +1,55 @@
+//! Generates short, human-memorable slugs (e.g. `"amber-otter"`) used as the
+//! default name for a [`crate::persistence::ChatSession`] when it is first
+//! saved.
+
+const ADJECTIVES: &[&str] = &[
+    "amber", "brave", "calm", "dusty", "eager", "fuzzy", "gentle", "hazy", "icy", "jolly", "keen",
+    "lively", "misty", "nimble", "odd", "proud", "quiet", "rusty", "sandy", "tidy", "upbeat",
+    "vivid", "wild", "young", "zesty",
+];
+
+const NOUNS: &[&str] = &[
+    "otter", "falcon", "maple", "comet", "delta", "ember", "fox", "glacier", "heron", "ibis",
+    "jaguar", "kestrel", "lynx", "meadow", "nebula", "oak", "pebble", "quokka", "raven", "salmon",
+    "tundra", "urchin", "valley", "willow", "yak",
+];
+
+const MAX_ATTEMPTS: u32 = 20;
+
+/// A tiny xorshift64 PRNG. We don't pull in the `rand` crate just to pick two
+/// words, and we need a seed-able generator so it can be driven by `Date::now()`.
+struct Xorshift64 {
+    state: u64,
+}
+
+impl Xorshift64 {
+    fn new(seed: u64) -> Self {
+        // xorshift is undefined for a zero state.
+        Self { state: seed.max(1) }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+}
+
+/// Picks a random `adjective-noun` slug, retrying up to [`MAX_ATTEMPTS`] times
+/// if the candidate collides with `existing_names`. Falls back to a
+/// timestamp-based name if every attempt collides.
+pub fn generate_session_name(seed: u64, existing_names: &[String]) -> String {
+    let mut rng = Xorshift64::new(seed);
+    for _ in 0..MAX_ATTEMPTS {
+        let adjective = ADJECTIVES[(rng.next_u64() as usize) % ADJECTIVES.len()];
+        let noun = NOUNS[(rng.next_u64() as usize) % NOUNS.len()];
+        let candidate = format!("{adjective}-{noun}");
+        if !existing_names.iter().any(|n| n == &candidate) {
+            return candidate;
+        }
+    }
+    format!("session-{seed}")
+}
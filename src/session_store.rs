@@ -0,0 +1,187 @@
+//! Pluggable backend for chat session persistence.
+//!
+//! `persistence.rs` talks to IndexedDB directly and is the production path,
+//! but that ties every caller to a wasm-only, IndexedDB-only storage layer:
+//! there's no way to exercise session persistence in tests, under SSR, or in
+//! a browser where IndexedDB is blocked (private mode, some WebViews). This
+//! module extracts the four operations the chat layer actually needs —
+//! `save`, `load`, `list_keys_by_update`, `delete` — behind [`SessionStore`],
+//! with [`IdbSessionStore`] as a thin wrapper around the existing
+//! `persistence` functions, plus an in-memory and a localStorage-backed
+//! fallback implementor.
+//!
+//! The more specialized operations (search, soft-delete/trash, pagination,
+//! the prompt library) stay as free functions in `persistence.rs` — they're
+//! IndexedDB-specific conveniences, not part of the portable core.
+
+use crate::persistence::{self, ChatSession};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Backend-agnostic chat session persistence. Implementors decide where
+/// sessions actually live; callers only depend on this trait, so the chat
+/// layer can choose a backend at startup without caring which one it got.
+///
+/// `?Send` because sessions hold wasm types (via `serde_wasm_bindgen`
+/// round-trips) and futures here never cross a thread boundary.
+#[async_trait(?Send)]
+pub trait SessionStore {
+    /// Saves (adds or updates) a session, keyed by its `session_id`.
+    async fn save(&self, session: &ChatSession) -> Result<()>;
+    /// Loads a session by id, or `None` if it doesn't exist.
+    async fn load(&self, session_id: &str) -> Result<Option<ChatSession>>;
+    /// Lists every session id, sorted by `updated_at_ms` descending (newest first).
+    async fn list_keys_by_update(&self) -> Result<Vec<String>>;
+    /// Deletes a session by id. A no-op if it doesn't exist.
+    async fn delete(&self, session_id: &str) -> Result<()>;
+}
+
+/// Production backend: delegates to the IndexedDB-backed functions in
+/// [`persistence`], which also keep the full-text search index in sync.
+pub struct IdbSessionStore;
+
+#[async_trait(?Send)]
+impl SessionStore for IdbSessionStore {
+    async fn save(&self, session: &ChatSession) -> Result<()> {
+        persistence::save_session(session).await
+    }
+
+    async fn load(&self, session_id: &str) -> Result<Option<ChatSession>> {
+        persistence::load_session(session_id).await
+    }
+
+    async fn list_keys_by_update(&self) -> Result<Vec<String>> {
+        persistence::get_all_session_keys_sorted_by_update().await
+    }
+
+    async fn delete(&self, session_id: &str) -> Result<()> {
+        persistence::delete_session(session_id).await
+    }
+}
+
+/// `HashMap`-backed store for unit-testing persistence logic off the wasm
+/// target, where IndexedDB isn't available at all. Single-threaded, like the
+/// rest of this app, so a plain `RefCell` is enough.
+#[derive(Default)]
+pub struct MemorySessionStore {
+    sessions: RefCell<HashMap<String, ChatSession>>,
+}
+
+impl MemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait(?Send)]
+impl SessionStore for MemorySessionStore {
+    async fn save(&self, session: &ChatSession) -> Result<()> {
+        self.sessions
+            .borrow_mut()
+            .insert(session.session_id.clone(), session.clone());
+        Ok(())
+    }
+
+    async fn load(&self, session_id: &str) -> Result<Option<ChatSession>> {
+        Ok(self.sessions.borrow().get(session_id).cloned())
+    }
+
+    async fn list_keys_by_update(&self) -> Result<Vec<String>> {
+        let mut sessions: Vec<ChatSession> = self.sessions.borrow().values().cloned().collect();
+        sessions.sort_by(|a, b| {
+            b.updated_at_ms
+                .partial_cmp(&a.updated_at_ms)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        Ok(sessions.into_iter().map(|s| s.session_id).collect())
+    }
+
+    async fn delete(&self, session_id: &str) -> Result<()> {
+        self.sessions.borrow_mut().remove(session_id);
+        Ok(())
+    }
+}
+
+/// Fallback store for browsers where IndexedDB is blocked: serializes every
+/// session into a single `localStorage` entry. Simple and always available,
+/// but every write re-serializes the whole map, so it doesn't scale the way
+/// `IdbSessionStore` does — only meant as a degraded-mode backstop.
+pub struct LocalStorageSessionStore {
+    storage_key: &'static str,
+}
+
+impl LocalStorageSessionStore {
+    pub fn new() -> Self {
+        Self {
+            storage_key: "tomatic_sessions_fallback",
+        }
+    }
+
+    fn read_all(&self) -> Result<HashMap<String, ChatSession>> {
+        let Some(storage) = window_local_storage()? else {
+            return Ok(HashMap::new());
+        };
+        let raw = storage
+            .get_item(self.storage_key)
+            .map_err(|e| anyhow!("[SessionStore] LocalStorage: Failed to read '{}': {:?}", self.storage_key, e))?;
+        match raw {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| anyhow!("[SessionStore] LocalStorage: Failed to deserialize sessions: {}", e)),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    fn write_all(&self, sessions: &HashMap<String, ChatSession>) -> Result<()> {
+        let json = serde_json::to_string(sessions)
+            .map_err(|e| anyhow!("[SessionStore] LocalStorage: Failed to serialize sessions: {}", e))?;
+        let storage = window_local_storage()?
+            .ok_or_else(|| anyhow!("[SessionStore] LocalStorage: localStorage is unavailable"))?;
+        storage
+            .set_item(self.storage_key, &json)
+            .map_err(|e| anyhow!("[SessionStore] LocalStorage: Failed to write '{}': {:?}", self.storage_key, e))?;
+        Ok(())
+    }
+}
+
+impl Default for LocalStorageSessionStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn window_local_storage() -> Result<Option<web_sys::Storage>> {
+    leptos::prelude::window()
+        .local_storage()
+        .map_err(|e| anyhow!("[SessionStore] LocalStorage: Failed to access localStorage: {:?}", e))
+}
+
+#[async_trait(?Send)]
+impl SessionStore for LocalStorageSessionStore {
+    async fn save(&self, session: &ChatSession) -> Result<()> {
+        let mut sessions = self.read_all()?;
+        sessions.insert(session.session_id.clone(), session.clone());
+        self.write_all(&sessions)
+    }
+
+    async fn load(&self, session_id: &str) -> Result<Option<ChatSession>> {
+        Ok(self.read_all()?.remove(session_id))
+    }
+
+    async fn list_keys_by_update(&self) -> Result<Vec<String>> {
+        let mut sessions: Vec<ChatSession> = self.read_all()?.into_values().collect();
+        sessions.sort_by(|a, b| {
+            b.updated_at_ms
+                .partial_cmp(&a.updated_at_ms)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        Ok(sessions.into_iter().map(|s| s.session_id).collect())
+    }
+
+    async fn delete(&self, session_id: &str) -> Result<()> {
+        let mut sessions = self.read_all()?;
+        sessions.remove(session_id);
+        self.write_all(&sessions)
+    }
+}
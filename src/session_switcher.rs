@@ -0,0 +1,241 @@
+use leptos::html;
+use leptos::prelude::*;
+use leptos_router::hooks::use_navigate;
+
+use crate::persistence::ChatSession;
+use crate::state::GlobalState;
+
+/// Scores `text` against `query` by rewarding a contiguous substring match
+/// highly, and falling back to an in-order subsequence match otherwise.
+/// Returns `None` if `query` isn't even a subsequence of `text`.
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let text_lower = text.to_lowercase();
+    let query_lower = query.to_lowercase();
+
+    if let Some(pos) = text_lower.find(&query_lower) {
+        // Substring matches are scored highest, earlier matches score higher.
+        return Some(1_000_000 - pos as i64);
+    }
+
+    // Subsequence match: every query char must appear in order in text.
+    let mut chars = text_lower.chars();
+    let mut matched = 0i64;
+    for qc in query_lower.chars() {
+        if chars.find(|tc| *tc == qc).is_some() {
+            matched += 1;
+        } else {
+            return None;
+        }
+    }
+    Some(matched)
+}
+
+fn first_user_message(session: &ChatSession) -> Option<String> {
+    session
+        .messages
+        .iter()
+        .find(|m| m.role == "user")
+        .map(|m| m.content.clone())
+}
+
+fn session_label(session: &ChatSession) -> String {
+    session
+        .name
+        .clone()
+        .unwrap_or_else(|| session.session_id.clone())
+}
+
+/// A fuzzy-search overlay for jumping directly to any past session by name or
+/// by its first user message, instead of walking `prev`/`next` one at a time.
+#[component]
+pub fn SessionSwitcher(
+    #[prop(into)] open: RwSignal<bool>,
+    #[prop(into)] sessions: Signal<Vec<ChatSession>>,
+) -> impl IntoView {
+    let state = use_context::<GlobalState>().expect("GlobalState not found");
+    let query = RwSignal::new(String::new());
+    let show_trash = RwSignal::new(false);
+    let input_ref: NodeRef<html::Input> = NodeRef::new();
+    let navigate = use_navigate();
+
+    let matches = Memo::new(move |_| {
+        let query = query.get();
+        let show_trash = show_trash.get();
+        let mut scored: Vec<(i64, ChatSession)> = sessions
+            .get()
+            .into_iter()
+            .filter(|session| session.deleted_at_ms.is_some() == show_trash)
+            .filter_map(|session| {
+                let name_score = fuzzy_score(&query, &session_label(&session));
+                let content_score = first_user_message(&session)
+                    .and_then(|content| fuzzy_score(&query, &content));
+                match (name_score, content_score) {
+                    (Some(a), Some(b)) => Some((a.max(b), session)),
+                    (Some(a), None) => Some((a, session)),
+                    (None, Some(b)) => Some((b, session)),
+                    (None, None) => None,
+                }
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0));
+        scored
+    });
+
+    Effect::new(move |_| {
+        if open.get() {
+            query.set(String::new());
+            if let Some(input) = input_ref.get() {
+                let _ = input.focus();
+            }
+        }
+    });
+
+    let go_to_session = move |session_id: String| {
+        open.set(false);
+        navigate(&format!("/chat/{session_id}"), Default::default());
+    };
+
+    view! {
+        <Show when=move || open.get()>
+            <div
+                class="session-switcher-backdrop"
+                style="position:fixed; inset:0; background:rgba(0,0,0,0.4); z-index:100; display:flex; align-items:flex-start; justify-content:center;"
+                on:click=move |_| open.set(false)
+            >
+                <div
+                    class="session-switcher"
+                    style="margin-top:10vh; width:min(560px, 90vw); background:var(--background-color); border:1px solid var(--border-color); border-radius:8px; overflow:hidden;"
+                    on:click=move |ev| ev.stop_propagation()
+                >
+                    <input
+                        type="text"
+                        node_ref=input_ref
+                        placeholder="Search sessions by name or message..."
+                        style="width:100%; padding:8px; border:none; border-bottom:1px solid var(--border-color);"
+                        prop:value=query
+                        on:input:target=move |ev| query.set(ev.target().value())
+                        on:keydown=move |ev| {
+                            if ev.key() == "Escape" {
+                                open.set(false);
+                            } else if ev.key() == "Enter" {
+                                if let Some((_, session)) = matches.get().into_iter().next() {
+                                    go_to_session(session.session_id);
+                                }
+                            }
+                        }
+                    />
+                    <div style="display:flex; justify-content:space-between; align-items:center; padding:4px 8px; border-bottom:1px solid var(--border-color);">
+                        <button
+                            data-size="compact"
+                            data-role="outline"
+                            on:click=move |_| show_trash.update(|v| *v = !*v)
+                        >
+                            {move || if show_trash.get() { "Show active" } else { "Show trash" }}
+                        </button>
+                        {move || {
+                            (!show_trash.get())
+                                .then(|| {
+                                    view! {
+                                        <button
+                                            data-size="compact"
+                                            data-role="destructive"
+                                            on:click=move |_| {
+                                                let confirmed = window()
+                                                    .confirm_with_message(
+                                                        "Delete all sessions? They can be restored from the trash until the grace period expires.",
+                                                    )
+                                                    .unwrap_or(false);
+                                                if confirmed {
+                                                    state.delete_all_sessions.run(());
+                                                }
+                                            }
+                                        >
+                                            "Delete all"
+                                        </button>
+                                    }
+                                })
+                        }}
+                    </div>
+                    <ul style="max-height:50vh; overflow-y:auto; margin:0; padding:0; list-style:none;">
+                        {move || {
+                            matches
+                                .get()
+                                .into_iter()
+                                .map(|(_, session)| {
+                                    let session_id = session.session_id.clone();
+                                    let session_id_for_action = session_id.clone();
+                                    let session_id_for_rename = session_id.clone();
+                                    let label = session_label(&session);
+                                    let label_for_rename = label.clone();
+                                    let preview = first_user_message(&session).unwrap_or_default();
+                                    let is_trashed = session.deleted_at_ms.is_some();
+                                    let on_rename = move |_| {
+                                        if let Ok(Some(name)) = window()
+                                            .prompt_with_message_and_default(
+                                                "Rename session:",
+                                                &label_for_rename,
+                                            )
+                                        {
+                                            let name = name.trim().to_string();
+                                            if !name.is_empty() {
+                                                state
+                                                    .rename_session
+                                                    .run((session_id_for_rename.clone(), name));
+                                            }
+                                        }
+                                    };
+                                    view! {
+                                        <li style="display:flex; align-items:center; gap:8px; padding:8px; border-bottom:1px solid var(--border-color);">
+                                            <div
+                                                style="flex-grow:1; cursor:pointer; overflow:hidden;"
+                                                on:click=move |_| go_to_session(session_id.clone())
+                                            >
+                                                <div style="font-weight:bold;">{label}</div>
+                                                <div style="font-size:0.85em; opacity:0.7; white-space:nowrap; overflow:hidden; text-overflow:ellipsis;">
+                                                    {preview}
+                                                </div>
+                                            </div>
+                                            {(!is_trashed)
+                                                .then(|| {
+                                                    view! {
+                                                        <button data-size="compact" data-role="outline" on:click=on_rename>
+                                                            "Rename"
+                                                        </button>
+                                                    }
+                                                })}
+                                            {if is_trashed {
+                                                view! {
+                                                    <button
+                                                        data-size="compact"
+                                                        on:click=move |_| state.restore_session.run(session_id_for_action.clone())
+                                                    >
+                                                        "Restore"
+                                                    </button>
+                                                }
+                                                    .into_any()
+                                            } else {
+                                                view! {
+                                                    <button
+                                                        data-size="compact"
+                                                        data-role="destructive"
+                                                        on:click=move |_| state.delete_session.run(session_id_for_action.clone())
+                                                    >
+                                                        "Delete"
+                                                    </button>
+                                                }
+                                                    .into_any()
+                                            }}
+                                        </li>
+                                    }
+                                })
+                                .collect_view()
+                        }}
+                    </ul>
+                </div>
+            </div>
+        </Show>
+    }
+}
@@ -1,31 +1,143 @@
+mod system_prompt;
+
 use leptos::prelude::*;
+use leptos::{html, task::spawn_local};
+use wasm_bindgen::JsCast;
 
-use crate::chat::types::SystemPrompt;
+use crate::dom_utils;
+use crate::persistence::PromptRecord;
+use crate::prompt_library::{export_json, import_json, parse_markdown_prompt, search_prompts};
+use crate::provider::ProviderKind;
+use crate::state::GlobalState;
+use crate::usage::UsageSummary;
+use system_prompt::SystemPromptItem;
+use uuid::Uuid;
+use web_sys::js_sys::Date;
 
 #[component]
 pub fn Settings(
     #[prop(into)] api_key: Signal<String>,
     #[prop(into)] set_api_key: WriteSignal<String>,
-    #[prop(into)] system_prompts: Signal<Vec<SystemPrompt>>,
-    #[prop(into)] set_system_prompts: WriteSignal<Vec<SystemPrompt>>,
+    #[prop(into)] provider_kind: Signal<ProviderKind>,
+    #[prop(into)] set_provider_kind: WriteSignal<ProviderKind>,
 ) -> impl IntoView {
-    let on_name_change = move |index: usize, new_value: String| {
-        set_system_prompts.update(|items| {
-            if let Some(item) = items.get_mut(index) {
-                item.name = new_value;
-            }
+    let state = use_context::<GlobalState>().expect("GlobalState not found");
+    let query = RwSignal::new(String::new());
+
+    let matching_prompts = Memo::new(move |_| {
+        let prompts = state.prompt_library.get();
+        search_prompts(&prompts, &query.get(), None)
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>()
+    });
+
+    let on_new = move |_| {
+        let now = Date::now();
+        state.save_prompt.run(PromptRecord {
+            id: Uuid::new_v4().to_string(),
+            title: String::new(),
+            body: String::new(),
+            tags: vec![],
+            description: None,
+            variables: vec![],
+            created_at_ms: now,
+            updated_at_ms: now,
+            schema_version: 0,
         });
     };
 
-    let on_prompt_change = move |index: usize, new_value: String| {
-        set_system_prompts.update(|items| {
-            if let Some(item) = items.get_mut(index) {
-                item.prompt = new_value;
+    let on_save = move |mut prompt: PromptRecord| {
+        prompt.updated_at_ms = Date::now();
+        state.save_prompt.run(prompt);
+    };
+
+    let on_export = move |_| {
+        let prompts = state.prompt_library.get_untracked();
+        match export_json(&prompts) {
+            Ok(json) => {
+                // Shown in a prompt dialog (pre-filled, selected) so the user can copy it out.
+                let _ = window().prompt_with_message_and_default("Prompt library export (copy this):", &json);
             }
-        });
+            Err(e) => leptos::logging::log!("[ERROR] [Settings] Failed to export prompt library: {e}"),
+        }
+    };
+
+    let on_import = move |_| {
+        if let Ok(Some(json)) = window().prompt_with_message("Paste a prompt library export (JSON):") {
+            if json.is_empty() {
+                return;
+            }
+            match import_json(&json) {
+                Ok(prompts) => {
+                    for prompt in prompts {
+                        state.save_prompt.run(prompt);
+                    }
+                }
+                Err(e) => leptos::logging::log!("[ERROR] [Settings] Failed to import prompt library: {e}"),
+            }
+        }
+    };
+
+    // Imports each dropped/selected file as its own prompt, parsing it as a
+    // Markdown document with YAML frontmatter (see `parse_markdown_prompt`).
+    let import_markdown_files = move |files: web_sys::FileList| {
+        for i in 0..files.length() {
+            let Some(file) = files.get(i) else { continue };
+            spawn_local(async move {
+                let filename = file.name();
+                match dom_utils::read_file_as_text(&file).await {
+                    Ok(text) => {
+                        let fallback_name = filename.trim_end_matches(".md").to_string();
+                        let parsed = parse_markdown_prompt(&text, &fallback_name);
+                        let now = Date::now();
+                        state.save_prompt.run(PromptRecord {
+                            id: Uuid::new_v4().to_string(),
+                            title: parsed.title,
+                            body: parsed.body,
+                            tags: parsed.tags,
+                            description: parsed.description,
+                            variables: vec![],
+                            created_at_ms: now,
+                            updated_at_ms: now,
+                            schema_version: 0,
+                        });
+                    }
+                    Err(e) => leptos::logging::log!(
+                        "[ERROR] [Settings] Failed to read imported file '{filename}': {:?}",
+                        e
+                    ),
+                }
+            });
+        }
+    };
+
+    let md_file_input: NodeRef<html::Input> = NodeRef::new();
+    let on_import_md_click = move |_| {
+        if let Some(input) = md_file_input.get() {
+            input.click();
+        }
+    };
+    let on_md_file_input_change = move |ev: leptos::ev::Event| {
+        if let Some(input) = ev.target().and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok()) {
+            if let Some(files) = input.files() {
+                import_markdown_files(files);
+            }
+            input.set_value("");
+        }
+    };
+    let on_md_drop = move |ev: web_sys::DragEvent| {
+        ev.prevent_default();
+        if let Some(files) = ev.data_transfer().and_then(|dt| dt.files()) {
+            import_markdown_files(files);
+        }
+    };
+    let on_md_dragover = move |ev: web_sys::DragEvent| {
+        ev.prevent_default();
     };
 
     view! {
+        <UsageSummary usage=state.usage />
         <settings-section>
             <settings-label>"OPENROUTER_API_KEY"</settings-label>
             <input
@@ -36,55 +148,59 @@ pub fn Settings(
             />
         </settings-section>
         <settings-section>
-            <settings-label>"system prompts"</settings-label>
-            <button
-                on:click=move |_| {
-                    set_system_prompts
-                        .update(|items| {
-                            items.insert(0, SystemPrompt::default());
-                        })
+            <settings-label>"Provider"</settings-label>
+            <select on:change:target=move |ev| {
+                if let Some(kind) = ProviderKind::ALL.get(ev.target().selected_index().max(0) as usize) {
+                    set_provider_kind.set(*kind);
                 }
-                style:margin-bottom="20px"
-            >
-
-                "New"
-            </button>
+            }>
+                {ProviderKind::ALL
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, kind)| {
+                        view! {
+                            <option value=i.to_string() selected=move || provider_kind.get() == kind>
+                                {kind.label()}
+                            </option>
+                        }
+                    })
+                    .collect_view()}
+            </select>
+        </settings-section>
+        <settings-section on:dragover=on_md_dragover on:drop=on_md_drop>
+            <settings-label>"system prompts"</settings-label>
+            <div style="display:flex; gap:8px; margin-bottom:20px; align-items:center;">
+                <button on:click=on_new>"New"</button>
+                <button data-role="outline" on:click=on_export>"Export"</button>
+                <button data-role="outline" on:click=on_import>"Import"</button>
+                <button data-role="outline" on:click=on_import_md_click>"Import .md"</button>
+                <input
+                    node_ref=md_file_input
+                    type="file"
+                    multiple
+                    accept=".md,text/markdown"
+                    style="display:none;"
+                    on:change=on_md_file_input_change
+                />
+                <input
+                    type="text"
+                    placeholder="Search prompts..."
+                    style="flex-grow:1;"
+                    prop:value=query
+                    on:input:target=move |ev| query.set(ev.target().value())
+                />
+            </div>
             {move || {
-                system_prompts
+                matching_prompts
                     .get()
-                    .iter()
-                    .enumerate()
-                    .map(|(index, value)| {
-                        let value = value.clone();
+                    .into_iter()
+                    .map(|prompt| {
                         view! {
-                            <settings-system-prompt>
-                                <div>
-                                    <input
-                                        type="text"
-                                        placeholder="name"
-                                        prop:value=value.name
-                                        on:input:target=move |ev| {
-                                            let input_value = ev.target().value();
-                                            on_name_change(index, input_value);
-                                        }
-                                        style:margin-bottom="4px"
-                                    />
-                                    <textarea
-                                        placeholder="system prompt"
-                                        prop:value=value.prompt
-                                        on:input:target=move |ev| {
-                                            let input_value = ev.target().value();
-                                            on_prompt_change(index, input_value);
-                                        }
-                                    />
-                                </div>
-                                <button on:click=move |_| {
-                                    set_system_prompts
-                                        .update(|items| {
-                                            items.remove(index);
-                                        })
-                                }>"Remove"</button>
-                            </settings-system-prompt>
+                            <SystemPromptItem
+                                value=prompt
+                                on_save=on_save
+                                on_delete=move |id: String| state.delete_prompt.run(id)
+                            />
                         }
                     })
                     .collect_view()
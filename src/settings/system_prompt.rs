@@ -1,31 +1,23 @@
-use crate::chat::types::SystemPrompt;
-use crate::settings::SettingsContext;
+use crate::dom_utils;
+use crate::persistence::PromptRecord;
+use crate::prompt_library::to_markdown_prompt;
 use leptos::{ev::MouseEvent, prelude::*};
 
 #[component]
 pub fn SystemPromptItem(
-    index: usize,
-    value: SystemPrompt,
-    set_system_prompts: WriteSignal<Vec<SystemPrompt>>,
+    value: PromptRecord,
+    #[prop(into)] on_save: Callback<PromptRecord>,
+    #[prop(into)] on_delete: Callback<String>,
 ) -> impl IntoView {
     let is_editing = RwSignal::new(false);
-    let editing_name = RwSignal::new(value.name.clone());
-    let editing_prompt = RwSignal::new(value.prompt.clone());
-
-    let settings = use_context::<SettingsContext>().unwrap();
-
-    Effect::new(move |_| {
-        if settings.editing_index.get() == Some(index) {
-            is_editing.set(true);
-            settings.editing_index.set(None);
-        }
-    });
+    let editing_title = RwSignal::new(value.title.clone());
+    let editing_body = RwSignal::new(value.body.clone());
 
     let turn_on_editing = {
         let value = value.clone();
         Callback::new(move |_: MouseEvent| {
-            editing_name.set(value.name.clone());
-            editing_prompt.set(value.prompt.clone());
+            editing_title.set(value.title.clone());
+            editing_body.set(value.body.clone());
             is_editing.set(true);
         })
     };
@@ -34,30 +26,35 @@ pub fn SystemPromptItem(
         is_editing.set(false);
     });
 
-    let on_save = {
+    let on_save_click = {
+        let value = value.clone();
         Callback::new(move |_: MouseEvent| {
-            set_system_prompts.update(|prompts| {
-                if let Some(prompt) = prompts.get_mut(index) {
-                    prompt.name = editing_name.get_untracked();
-                    prompt.prompt = editing_prompt.get_untracked();
-                }
+            on_save.run(PromptRecord {
+                title: editing_title.get_untracked(),
+                body: editing_body.get_untracked(),
+                ..value.clone()
             });
             turn_off_editing.run(());
         })
     };
 
-    let on_cancel = {
-        Callback::new(move |_: MouseEvent| {
-            turn_off_editing.run(());
-        })
-    };
+    let on_cancel = Callback::new(move |_: MouseEvent| {
+        turn_off_editing.run(());
+    });
 
+    let prompt_id = value.id.clone();
     let on_remove = Callback::new(move |_: MouseEvent| {
-        set_system_prompts.update(|prompts| {
-            if index < prompts.len() {
-                prompts.remove(index);
-            }
-        })
+        on_delete.run(prompt_id.clone());
+    });
+
+    let value_for_export = value.clone();
+    let on_export_md = Callback::new(move |_: MouseEvent| {
+        let filename = if value_for_export.title.is_empty() {
+            "prompt.md".to_string()
+        } else {
+            format!("{}.md", value_for_export.title)
+        };
+        dom_utils::download_text_file(&filename, &to_markdown_prompt(&value_for_export));
     });
 
     view! {
@@ -66,12 +63,15 @@ pub fn SystemPromptItem(
             fallback=move || {
                 view! {
                     <div class="system-prompt-item-view">
-                        <span class="system-prompt-name">{value.name.clone()}</span>
-                        <span class="system-prompt-text">{value.prompt.clone()}</span>
+                        <span class="system-prompt-name">{value.title.clone()}</span>
+                        <span class="system-prompt-text">{value.body.clone()}</span>
                         <div class="system-prompt-buttons">
                             <button on:click=move |ev| turn_on_editing.run(ev) data-size="compact">
                                 "Edit"
                             </button>
+                            <button on:click=move |ev| on_export_md.run(ev) data-size="compact">
+                                "Export .md"
+                            </button>
                             <button on:click=move |ev| on_remove.run(ev) data-size="compact">
                                 "Delete"
                             </button>
@@ -84,19 +84,19 @@ pub fn SystemPromptItem(
                 <div class="system-prompt-inputs">
                     <input
                         type="text"
-                        prop:value=editing_name
-                        on:input:target=move |ev| editing_name.set(ev.target().value())
+                        prop:value=editing_title
+                        on:input:target=move |ev| editing_title.set(ev.target().value())
                         placeholder="name"
                     />
                     <textarea
-                        prop:value=editing_prompt
-                        on:input:target=move |ev| editing_prompt.set(ev.target().value())
+                        prop:value=editing_body
+                        on:input:target=move |ev| editing_body.set(ev.target().value())
                         placeholder="system prompt"
                     />
                 </div>
                 <div class="system-prompt-edit-buttons">
                     <button
-                        on:click=move |ev| on_save.run(ev)
+                        on:click=move |ev| on_save_click.run(ev)
                         data-size="compact"
                         data-role="primary"
                     >
@@ -109,4 +109,4 @@ pub fn SystemPromptItem(
             </div>
         </Show>
     }
-}
\ No newline at end of file
+}
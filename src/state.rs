@@ -1,6 +1,8 @@
 use leptos::prelude::*;
-use crate::chat::types::{Message, SystemPrompt};
-use crate::llm::DisplayModelInfo;
+use crate::chat::types::{Message, MessageCost, SystemPrompt};
+use crate::llm::{DisplayModelInfo, Usage};
+use crate::persistence::{ChatSession, PromptRecord, UsageRollup};
+use crate::provider::ProviderKind;
 
 #[derive(Clone)]
 pub struct GlobalState {
@@ -8,15 +10,37 @@ pub struct GlobalState {
     pub api_key: Signal<String>,
     pub set_api_key: WriteSignal<String>,
     pub system_prompts: Signal<Vec<SystemPrompt>>,
-    pub set_system_prompts: WriteSignal<Vec<SystemPrompt>>,
+    // Prompt library, backed by IndexedDB (see `persistence::PromptRecord`).
+    // `system_prompts` above is derived from this for the existing chat UI.
+    pub prompt_library: RwSignal<Vec<PromptRecord>>,
+    pub save_prompt: Callback<PromptRecord>,
+    pub delete_prompt: Callback<String>,
     pub model_name: Signal<String>,
     pub set_model_name: WriteSignal<String>,
+    /// Which backend `Model.provider` is sourced from; selected in
+    /// `Settings`, persisted like `model_name`.
+    pub provider_kind: Signal<ProviderKind>,
+    pub set_provider_kind: WriteSignal<ProviderKind>,
     pub input: Signal<String>,
     pub set_input: WriteSignal<String>,
     pub cached_models: Signal<Vec<DisplayModelInfo>>,
     pub set_cached_models: WriteSignal<Vec<DisplayModelInfo>>,
+    /// Optional USD ceiling checked against the projected cost of a request
+    /// before it's sent; exceeding it blocks submission via `set_error`.
+    pub budget_ceiling_usd: Signal<Option<f64>>,
+    pub set_budget_ceiling_usd: WriteSignal<Option<f64>>,
     // Current session state
     pub messages: RwSignal<Vec<Message>>,
+    /// Every message across every branch of the current session — the
+    /// merged tree `persistence::ChatSession::messages` represents.
+    /// `messages` above is always the resolved view of whichever path is
+    /// active; this is kept around so `switch_branch` has siblings to offer
+    /// without a DB round trip.
+    pub all_messages: RwSignal<Vec<Message>>,
+    /// Switches the active conversation to the branch containing the given
+    /// message id (e.g. an alternate regenerated reply), then saves so the
+    /// new active path persists.
+    pub switch_branch: Callback<String>,
     pub selected_prompt_name: RwSignal<Option<String>>,
     pub error: RwSignal<Option<String>>,
     pub current_session_id: RwSignal<Option<String>>,
@@ -24,4 +48,29 @@ pub struct GlobalState {
     pub session_load_request: WriteSignal<Option<String>>,
     pub navigation_request: RwSignal<Option<String>>,
     pub initial_chat_prompt: RwSignal<Option<String>>,
+    // Session switcher
+    pub all_sessions: RwSignal<Vec<ChatSession>>,
+    pub session_switcher_open: RwSignal<bool>,
+    pub rename_session: Callback<(String, String)>,
+    pub delete_session: Callback<String>,
+    pub restore_session: Callback<String>,
+    pub delete_all_sessions: Callback<()>,
+    // Command palette
+    pub command_palette_open: RwSignal<bool>,
+    /// Set by `ChatInterface` while a request is streaming, so the command
+    /// palette can offer a "Cancel in-flight request" action.
+    pub cancel_request: RwSignal<Option<Callback<()>>>,
+    /// Whether the `PromptPicker` overlay (opened from `Header`, or via the
+    /// `keymap` shortcut layer) is currently shown.
+    pub prompt_picker_open: RwSignal<bool>,
+    // Cost analytics (see `crate::usage`)
+    /// Daily per-model cost rollups, backed by `persistence::UsageRollup`.
+    /// Loaded once at startup and kept current by `record_usage` below
+    /// rather than re-fetched after every message.
+    pub usage: RwSignal<Vec<UsageRollup>>,
+    /// Folds a just-settled `Usage`/`MessageCost` into `usage` (creating or
+    /// updating today's rollup for `model_name`) and persists the change.
+    /// Called wherever `handle_llm_request`/`regenerate_llm_request`/
+    /// `continue_llm_request` write a populated `Message::cost`.
+    pub record_usage: Callback<(String, Usage, MessageCost)>,
 }
@@ -0,0 +1,153 @@
+//! Token counting for the chat composer's live budget indicator (see
+//! `ChatControls`), implemented as real byte-pair encoding: `estimate_tokens`
+//! pretokenizes the way cl100k_base's splitting regex roughly does, then
+//! repeatedly merges the adjacent byte-sequence pair with the lowest rank in
+//! [`merge_ranks`] until no mergeable pair remains, same as a real BPE
+//! encoder (the final piece count is the token count; we don't need the
+//! merged token ids themselves, only how many pieces they collapse to).
+//!
+//! The merge-rank table is **not** OpenAI's cl100k_base table: that's a
+//! ~100k-entry asset this sandbox has no way to fetch or vendor. What's
+//! embedded in [`merge_ranks`] is a small, hand-curated subset covering
+//! common English letter pairs and a handful of whole-word merges built on
+//! top of them, roughly most-frequent-first the way a trained BPE table
+//! would order them. The algorithm is the genuine thing; the table isn't —
+//! so counts are real BPE token counts *under this table*, not the official
+//! cl100k_base count, and trend higher than the real thing for anything the
+//! embedded merges don't cover (non-English text, rare words, code). Good
+//! enough for a "you're getting close to the context window" warning; not
+//! good enough to match what the API actually bills. `cost_tracker.rs`'s
+//! similar byte-length heuristic is a separate, independent estimate, not a
+//! second BPE implementation corroborating this one.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Pretokenizes `text` into chunks the way cl100k_base's splitting regex
+/// roughly does: a run of word characters, a run of whitespace, or a single
+/// punctuation character.
+fn pretokenize(text: &str) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some(&(i, c)) = chars.peek() {
+        if c.is_whitespace() {
+            while let Some(&(_, c)) = chars.peek() {
+                if !c.is_whitespace() {
+                    break;
+                }
+                chars.next();
+            }
+        } else if c.is_alphanumeric() || c == '_' {
+            while let Some(&(_, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_') {
+                    break;
+                }
+                chars.next();
+            }
+        } else {
+            chars.next();
+        }
+        let end = chars.peek().map_or(text.len(), |&(j, _)| j);
+        chunks.push(&text[i.min(start)..end]);
+        start = end;
+    }
+    chunks
+}
+
+/// `(left, right) -> rank`: lower rank merges first, mirroring how a trained
+/// BPE merge table is ordered by pair frequency. See the module doc for why
+/// this is a small hand-curated subset rather than the real cl100k_base
+/// table. Built in two tiers: single-byte letter pairs first, then a few
+/// merges of those pairs with another letter or pair to form common whole
+/// words (e.g. `th` + `e` -> `the`), same layering a real merge table has.
+fn merge_ranks() -> &'static HashMap<(Vec<u8>, Vec<u8>), u32> {
+    static RANKS: OnceLock<HashMap<(Vec<u8>, Vec<u8>), u32>> = OnceLock::new();
+    RANKS.get_or_init(|| {
+        const MERGES: &[(&[u8], &[u8])] = &[
+            // Tier 1: common English letter-pair merges.
+            (b"t", b"h"),
+            (b"i", b"n"),
+            (b"e", b"r"),
+            (b"a", b"n"),
+            (b"r", b"e"),
+            (b"o", b"n"),
+            (b"a", b"t"),
+            (b"e", b"n"),
+            (b"o", b"r"),
+            (b"i", b"s"),
+            (b"e", b"s"),
+            (b"i", b"t"),
+            (b"o", b"u"),
+            (b"a", b"r"),
+            (b"s", b"t"),
+            (b"n", b"d"),
+            (b"t", b"o"),
+            (b"n", b"t"),
+            (b"n", b"g"),
+            (b"a", b"l"),
+            (b"s", b"e"),
+            (b"h", b"a"),
+            (b"v", b"e"),
+            (b"c", b"o"),
+            (b"w", b"h"),
+            (b"y", b"o"),
+            (b"o", b"f"),
+            (b"l", b"e"),
+            // Tier 2: whole common words built from tier-1 pairs.
+            (b"th", b"e"),
+            (b"th", b"is"),
+            (b"th", b"at"),
+            (b"wh", b"at"),
+            (b"wh", b"en"),
+            (b"yo", b"u"),
+            (b"an", b"d"),
+            (b"no", b"t"),
+            (b"in", b"g"),
+            (b"ha", b"ve"),
+            (b"wit", b"h"),
+            (b"fo", b"r"),
+            (b"ar", b"e"),
+            (b"fr", b"om"),
+        ];
+        MERGES
+            .iter()
+            .enumerate()
+            .map(|(rank, &(left, right))| ((left.to_vec(), right.to_vec()), rank as u32))
+            .collect()
+    })
+}
+
+/// Runs the encode loop of a BPE tokenizer over `chunk`'s raw bytes: start
+/// with one piece per byte, then repeatedly merge the adjacent pair with the
+/// lowest rank in [`merge_ranks`] until no adjacent pair is in the table.
+/// Returns the resulting piece count (the token count for this chunk).
+fn bpe_piece_count(chunk: &str) -> usize {
+    let ranks = merge_ranks();
+    let mut pieces: Vec<Vec<u8>> = chunk.bytes().map(|b| vec![b]).collect();
+
+    loop {
+        let mut best: Option<(usize, u32)> = None;
+        for i in 0..pieces.len().saturating_sub(1) {
+            let pair = (pieces[i].clone(), pieces[i + 1].clone());
+            if let Some(&rank) = ranks.get(&pair) {
+                if best.map_or(true, |(_, best_rank)| rank < best_rank) {
+                    best = Some((i, rank));
+                }
+            }
+        }
+        let Some((i, _)) = best else { break };
+        let merged = [pieces[i].as_slice(), pieces[i + 1].as_slice()].concat();
+        pieces.splice(i..=i + 1, [merged]);
+    }
+
+    pieces.len().max(1)
+}
+
+/// Estimates the number of tokens in `text` via byte-pair encoding. See the
+/// module doc for why this is an estimate rather than an exact cl100k_base
+/// count.
+pub fn estimate_tokens(text: &str) -> usize {
+    pretokenize(text).into_iter().map(bpe_piece_count).sum()
+}
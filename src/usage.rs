@@ -0,0 +1,95 @@
+//! Cost analytics derived from `persistence::UsageRollup`, the day/model
+//! rollups `record_usage` keeps current whenever a message's cost settles.
+//!
+//! Complements [`crate::cost_tracker`]'s live per-session estimate: this
+//! module totals *actual*, already-billed cost (from [`crate::llm::Usage`])
+//! across every session ever recorded, not just the one on screen.
+
+use leptos::prelude::*;
+
+use crate::persistence::UsageRollup;
+
+/// Total USD spent across every recorded rollup.
+pub fn total_cost_usd(rollups: &[UsageRollup]) -> f64 {
+    rollups
+        .iter()
+        .map(|r| r.prompt_cost_usd + r.completion_cost_usd)
+        .sum()
+}
+
+/// Per-model spend, summed across all days, sorted highest-spend first.
+pub fn top_models_by_spend(rollups: &[UsageRollup]) -> Vec<(String, f64)> {
+    let mut by_model: std::collections::HashMap<String, f64> = std::collections::HashMap::new();
+    for r in rollups {
+        *by_model.entry(r.model_name.clone()).or_default() += r.prompt_cost_usd + r.completion_cost_usd;
+    }
+    let mut models: Vec<(String, f64)> = by_model.into_iter().collect();
+    models.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    models
+}
+
+/// Per-day spend, summed across all models, sorted oldest-first (so a
+/// sparkline reads left-to-right as a timeline).
+pub fn daily_cost_usd(rollups: &[UsageRollup]) -> Vec<(String, f64)> {
+    let mut by_day: std::collections::HashMap<String, f64> = std::collections::HashMap::new();
+    for r in rollups {
+        *by_day.entry(r.date.clone()).or_default() += r.prompt_cost_usd + r.completion_cost_usd;
+    }
+    let mut days: Vec<(String, f64)> = by_day.into_iter().collect();
+    days.sort_by(|a, b| a.0.cmp(&b.0));
+    days
+}
+
+/// Settings-reachable breakdown of historical spend: a running total, the
+/// top models by cost, and a sparkline of daily cost.
+#[component]
+pub fn UsageSummary(#[prop(into)] usage: Signal<Vec<UsageRollup>>) -> impl IntoView {
+    let total = Memo::new(move |_| total_cost_usd(&usage.get()));
+    let top_models = Memo::new(move |_| top_models_by_spend(&usage.get()));
+    let daily = Memo::new(move |_| daily_cost_usd(&usage.get()));
+
+    view! {
+        <settings-section>
+            <settings-label>"usage"</settings-label>
+            <div style="font-size:0.9em; margin-bottom:12px;">
+                {move || format!("${:.4} total across all sessions", total.get())}
+            </div>
+            <div style="display:flex; flex-direction:column; gap:4px; margin-bottom:12px;">
+                {move || {
+                    top_models
+                        .get()
+                        .into_iter()
+                        .take(5)
+                        .map(|(model_name, cost)| {
+                            view! {
+                                <div style="display:flex; justify-content:space-between; font-size:0.85em;">
+                                    <span>{model_name}</span>
+                                    <span>{format!("${cost:.4}")}</span>
+                                </div>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </div>
+            <div style="display:flex; align-items:flex-end; gap:2px; height:32px;">
+                {move || {
+                    let days = daily.get();
+                    let max_cost = days.iter().map(|(_, c)| *c).fold(0.0_f64, f64::max).max(0.0001);
+                    days.into_iter()
+                        .map(|(date, cost)| {
+                            let height_pct = (cost / max_cost * 100.0).max(2.0);
+                            view! {
+                                <div
+                                    title=format!("{date}: ${cost:.4}")
+                                    style=format!(
+                                        "width:4px; height:{height_pct}%; background:currentColor; opacity:0.6;",
+                                    )
+                                ></div>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </div>
+        </settings-section>
+    }
+}